@@ -8,7 +8,138 @@ use syn::Error as SynError;
 #[derive(Debug)]
 pub enum MacroError {
     Syn(SynError),
-    NoEndpointsConfigured { span: Span },
+    NoEndpointsConfigured {
+        span: Span,
+    },
+    RequiresRequestBody {
+        span: Span,
+        field: &'static str,
+    },
+    RequiresResponseBody {
+        span: Span,
+        field: &'static str,
+    },
+    RequiresHeaders {
+        span: Span,
+        field: &'static str,
+    },
+    /// An empty endpoint `path`, which would send every call to the
+    /// provider's URL itself; leave `path` out for that instead:
+    ///
+    /// ```compile_fail
+    /// use http_provider_macro::http_provider;
+    ///
+    /// http_provider!(
+    ///     UserApi,
+    ///     {
+    ///         {
+    ///             path: "",
+    ///             method: GET,
+    ///             res_format: status,
+    ///         },
+    ///     }
+    /// );
+    /// ```
+    InvalidPath {
+        span: Span,
+    },
+    /// An endpoint `host` that is not an absolute HTTP URL, checked when the
+    /// macro expands:
+    ///
+    /// ```compile_fail
+    /// use http_provider_macro::http_provider;
+    ///
+    /// http_provider!(
+    ///     AuthApi,
+    ///     {
+    ///         {
+    ///             path: "/token",
+    ///             method: GET,
+    ///             host: "https://",
+    ///             res_format: status,
+    ///         },
+    ///     }
+    /// );
+    /// ```
+    InvalidHost {
+        span: Span,
+        reason: String,
+    },
+    InvalidBatch {
+        span: Span,
+    },
+    ReservedFieldName {
+        span: Span,
+        name: String,
+    },
+    /// The setter generated for a field, e.g. `with_header` for a `sign_body`
+    /// secret named `header`, is a method of the generated provider. The
+    /// example only checks this with the `hmac` feature, which `sign_body`
+    /// needs:
+    ///
+    #[cfg_attr(feature = "hmac", doc = "```compile_fail")]
+    #[cfg_attr(not(feature = "hmac"), doc = "```ignore")]
+    /// use http_provider_macro::http_provider;
+    ///
+    /// #[derive(serde::Serialize)]
+    /// struct Event;
+    ///
+    /// http_provider!(
+    ///     HookApi,
+    ///     {
+    ///         {
+    ///             path: "/hooks",
+    ///             method: POST,
+    ///             req: Event,
+    ///             sign_body: { header: "X-Signature", secret_field: header },
+    ///         },
+    ///     }
+    /// );
+    /// ```
+    ReservedSetterName {
+        span: Span,
+        setter: String,
+    },
+    /// Two generated methods share a name, e.g. the `*_request` variant of
+    /// `GET /users` and the method of `GET /users/request`:
+    ///
+    /// ```compile_fail
+    /// use http_provider_macro::http_provider;
+    ///
+    /// http_provider!(
+    ///     UserApi,
+    ///     {
+    ///         { path: "/users", method: GET, res_format: status, },
+    ///         { path: "/users/request", method: GET, res_format: status, },
+    ///     }
+    /// );
+    /// ```
+    DuplicateMethod {
+        span: Span,
+        name: String,
+    },
+    UnexpectedRequestBody {
+        span: Span,
+        method: String,
+    },
+    /// Two options cannot be combined, e.g. a `res` type with
+    /// `res_format: status`, which returns no body:
+    ///
+    /// ```compile_fail
+    /// use http_provider_macro::http_provider;
+    ///
+    /// http_provider!(
+    ///     UserApi,
+    ///     {
+    ///         { path: "/users", method: GET, res: String, res_format: status, },
+    ///     }
+    /// );
+    /// ```
+    IncompatibleFields {
+        span: Span,
+        field: &'static str,
+        other: &'static str,
+    },
 }
 
 impl MacroError {
@@ -19,12 +150,77 @@ impl MacroError {
     ///
     /// # Returns
     /// * `proc_macro2::TokenStream` - A token stream representing the error message
+    #[allow(clippy::wrong_self_convention)]
     pub fn to_compile_error(self) -> proc_macro2::TokenStream {
         match self {
             MacroError::Syn(err) => err.to_compile_error(),
             MacroError::NoEndpointsConfigured { span } => {
                 SynError::new(span, "at least one endpoint must be defined").to_compile_error()
             }
+            MacroError::RequiresRequestBody { span, field } => {
+                SynError::new(span, format!("`{}` requires a `req` type", field)).to_compile_error()
+            }
+            MacroError::RequiresResponseBody { span, field } => {
+                SynError::new(span, format!("`{}` requires a `res` type", field)).to_compile_error()
+            }
+            MacroError::RequiresHeaders { span, field } => {
+                SynError::new(span, format!("`{}` requires a `headers` type", field))
+                    .to_compile_error()
+            }
+            MacroError::InvalidPath { span } => {
+                SynError::new(span, "endpoint path must not be empty")
+                    .to_compile_error()
+            }
+            MacroError::InvalidHost { span, reason } => SynError::new(
+                span,
+                format!(
+                    "endpoint host must be an absolute `http://` or `https://` URL: {}",
+                    reason
+                ),
+            )
+            .to_compile_error(),
+            MacroError::InvalidBatch { span } => SynError::new(
+                span,
+                "`batch` endpoints need `req: Vec<Op>` and `res: Vec<OpResult>`",
+            )
+            .to_compile_error(),
+            MacroError::ReservedFieldName { span, name } => SynError::new(
+                span,
+                format!(
+                    "`{}` is a field of the generated provider; choose another name",
+                    name
+                ),
+            )
+            .to_compile_error(),
+            MacroError::ReservedSetterName { span, setter } => SynError::new(
+                span,
+                format!(
+                    "its setter `{}` is a method of the generated provider; choose another name",
+                    setter
+                ),
+            )
+            .to_compile_error(),
+            MacroError::DuplicateMethod { span, name } => SynError::new(
+                span,
+                format!(
+                    "the method `{}` is generated more than once; rename the endpoint with `fn_name`",
+                    name
+                ),
+            )
+            .to_compile_error(),
+            MacroError::UnexpectedRequestBody { span, method } => SynError::new(
+                span,
+                format!(
+                    "`req` on a {} endpoint is unusual; remove it or drop `strict`",
+                    method
+                ),
+            )
+            .to_compile_error(),
+            MacroError::IncompatibleFields { span, field, other } => SynError::new(
+                span,
+                format!("`{}` cannot be combined with `{}`", field, other),
+            )
+            .to_compile_error(),
         }
     }
 }
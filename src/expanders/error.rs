@@ -2,17 +2,62 @@ use proc_macro2::TokenStream;
 use quote::quote;
 use syn::Ident;
 
+use crate::input::ErrorBody;
+
 pub struct ErrorExpander<'a> {
     error_name: &'a Ident,
+    error_bodies: Vec<&'a ErrorBody>,
+    graphql_error: Option<&'a Ident>,
 }
 
 impl<'a> ErrorExpander<'a> {
-    pub fn new(error_name: &'a Ident) -> Self {
-        Self { error_name }
+    /// `error_bodies` are the typed error bodies of the endpoints; one variant
+    /// is generated per distinct variant name. `graphql_error` names the
+    /// GraphQL error struct when an endpoint uses `graphql`.
+    pub fn new(
+        error_name: &'a Ident,
+        error_bodies: impl IntoIterator<Item = &'a ErrorBody>,
+        graphql_error: Option<&'a Ident>,
+    ) -> Self {
+        let mut unique: Vec<&ErrorBody> = Vec::new();
+        for error_body in error_bodies {
+            if !unique.iter().any(|seen| seen.variant == error_body.variant) {
+                unique.push(error_body);
+            }
+        }
+        Self {
+            error_name,
+            error_bodies: unique,
+            graphql_error,
+        }
     }
 
     pub fn expand(&self) -> TokenStream {
         let error_name = self.error_name;
+        let variants = self.error_bodies.iter().map(|error_body| {
+            let variant = &error_body.variant;
+            let ty = &error_body.ty;
+            quote! { #variant { status: u16, body: #ty }, }
+        });
+        let display_arms = self.error_bodies.iter().map(|error_body| {
+            let variant = &error_body.variant;
+            quote! {
+                Self::#variant { status, body } => write!(f, "HTTP {} {:?}", status, body),
+            }
+        });
+        let (graphql_variant, graphql_display) = match self.graphql_error {
+            Some(graphql_error) => (
+                quote! { GraphQlErrors(Vec<#graphql_error>), },
+                quote! {
+                    Self::GraphQlErrors(errors) => {
+                        let messages: Vec<&str> =
+                            errors.iter().map(|error| error.message.as_str()).collect();
+                        write!(f, "GraphQL errors: {}", messages.join("; "))
+                    }
+                },
+            ),
+            None => (quote! {}, quote! {}),
+        };
 
         quote! {
             #[derive(Debug)]
@@ -21,6 +66,16 @@ impl<'a> ErrorExpander<'a> {
                 Request(reqwest::Error),
                 Http { status: u16, reason: String },
                 Deserialization(String),
+                Serialization(String),
+                Validation(String),
+                Config(String),
+                DeadlineExceeded,
+                Cancelled,
+                PreconditionFailed,
+                Signing(String),
+                Io(std::io::Error),
+                #(#variants)*
+                #graphql_variant
             }
 
             impl std::fmt::Display for #error_name {
@@ -30,6 +85,16 @@ impl<'a> ErrorExpander<'a> {
                         Self::Request(err) => write!(f, "Request failed: {}", err),
                         Self::Http { status, reason } => write!(f, "HTTP {} {}", status, reason),
                         Self::Deserialization(msg) => write!(f, "Failed to deserialize: {}", msg),
+                        Self::Serialization(msg) => write!(f, "Failed to serialize: {}", msg),
+                        Self::Validation(msg) => write!(f, "Validation failed: {}", msg),
+                        Self::Config(msg) => write!(f, "Invalid configuration: {}", msg),
+                        Self::DeadlineExceeded => write!(f, "Deadline exceeded"),
+                        Self::Cancelled => write!(f, "Cancelled"),
+                        Self::PreconditionFailed => write!(f, "Precondition failed"),
+                        Self::Signing(msg) => write!(f, "Failed to sign request: {}", msg),
+                        Self::Io(err) => write!(f, "I/O error: {}", err),
+                        #(#display_arms)*
+                        #graphql_display
                     }
                 }
             }
@@ -38,6 +103,7 @@ impl<'a> ErrorExpander<'a> {
                 fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
                     match self {
                         Self::Request(err) => Some(err),
+                        Self::Io(err) => Some(err),
                         _ => None,
                     }
                 }
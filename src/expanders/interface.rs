@@ -1,51 +1,144 @@
-use crate::{error::MacroResult, input::EndpointDef};
+use crate::{error::MacroResult, input::HttpProviderInput};
 use proc_macro2::TokenStream;
 use quote::quote;
 use syn::Ident;
 
-use super::method::{FnNameExpander, ParamsExpander};
+use super::method::{
+    trait_error_type, AttrsExpander, ClientFlavor, FnNameExpander, ParamsExpander,
+    SignatureExpander,
+};
 
 pub struct TraitExpander<'a> {
-    endpoints: &'a [EndpointDef],
+    input: &'a HttpProviderInput,
     trait_name: &'a Ident,
     error_name: &'a Ident,
+    flavor: ClientFlavor,
 }
 
 impl<'a> TraitExpander<'a> {
-    pub fn new(endpoints: &'a [EndpointDef], trait_name: &'a Ident, error_name: &'a Ident) -> Self {
+    pub fn new(
+        input: &'a HttpProviderInput,
+        trait_name: &'a Ident,
+        error_name: &'a Ident,
+        flavor: ClientFlavor,
+    ) -> Self {
         Self {
-            endpoints,
+            input,
             trait_name,
             error_name,
+            flavor,
         }
     }
 
     pub fn expand(&self) -> MacroResult<TokenStream> {
         let trait_name = self.trait_name;
         let trait_methods = self.expand_trait_methods();
+        let forwarded_methods = self.expand_forwarded_methods();
+        let routes = self.expand_routes();
+        let vis = self.input.options.trait_vis();
+        let error_name = self.error_name;
+        let (decl_generics, impl_generics, ty_generics) = if self.input.options.generic_error {
+            (
+                quote! { <E = #error_name> },
+                quote! { <'r, E, T: #trait_name<E> + ?Sized> },
+                quote! { <E> },
+            )
+        } else {
+            (
+                quote! {},
+                quote! { <'r, T: #trait_name + ?Sized> },
+                quote! {},
+            )
+        };
+        let bounds = &self.input.options.trait_bound;
+        let (supertraits, forwarded_bounds) = if bounds.is_empty() {
+            (quote! {}, quote! {})
+        } else {
+            (
+                quote! { : #(#bounds)+* },
+                quote! { where &'r T: #(#bounds)+* },
+            )
+        };
 
         Ok(quote! {
-            pub trait #trait_name {
+            // The endpoint types are often private to the invoking module.
+            #[allow(private_interfaces, private_bounds)]
+            #vis trait #trait_name #decl_generics #supertraits {
+                /// The `(method, path)` of every endpoint, in declaration order.
+                fn routes(&self) -> &'static [(&'static str, &'static str)] {
+                    &[#(#routes),*]
+                }
+
                 #(#trait_methods)*
             }
+
+            // Lets `&provider` satisfy `impl Trait` bounds in generic code.
+            #[allow(deprecated, private_interfaces)]
+            impl #impl_generics #trait_name #ty_generics for &'r T #forwarded_bounds {
+                fn routes(&self) -> &'static [(&'static str, &'static str)] {
+                    (**self).routes()
+                }
+
+                #(#forwarded_methods)*
+            }
         })
     }
 
-    fn expand_trait_methods(&self) -> Vec<TokenStream> {
-        self.endpoints
+    fn expand_routes(&self) -> Vec<TokenStream> {
+        self.input
+            .endpoints
             .iter()
             .map(|def| {
-                let fn_name = FnNameExpander::new(def).expand();
-                let params = ParamsExpander::new(def).expand();
-                let res = def
-                    .res
+                let method = def.method.verb();
+                let path = def
+                    .path
                     .as_ref()
-                    .map(|t| quote! { #t })
-                    .unwrap_or_else(|| quote! { () });
-                let error_name = self.error_name;
+                    .map_or_else(|| "/".to_string(), |p| p.value());
+                let cfg = AttrsExpander::new(def).expand_cfg();
+                quote! { #cfg (#method, #path) }
+            })
+            .collect()
+    }
+
+    fn expand_forwarded_methods(&self) -> Vec<TokenStream> {
+        let awaited = match self.flavor {
+            ClientFlavor::Async if !self.input.options.boxed_futures => quote! { .await },
+            _ => quote! {},
+        };
+        self.input
+            .endpoints
+            .iter()
+            .map(|def| {
+                let trait_error = trait_error_type(self.input, self.error_name);
+                let signature =
+                    SignatureExpander::new(def, self.input, &trait_error, self.flavor).expand();
+                let fn_name = FnNameExpander::new(def, &self.input.options).expand();
+                let args = ParamsExpander::new(def, self.input).expand_names();
+                let cfg = AttrsExpander::new(def).expand_cfg();
+
+                quote! {
+                    #cfg
+                    #signature {
+                        (**self).#fn_name(#(#args),*)#awaited
+                    }
+                }
+            })
+            .collect()
+    }
+
+    fn expand_trait_methods(&self) -> Vec<TokenStream> {
+        self.input
+            .endpoints
+            .iter()
+            .map(|def| {
+                let trait_error = trait_error_type(self.input, self.error_name);
+                let signature =
+                    SignatureExpander::new(def, self.input, &trait_error, self.flavor).expand();
+                let attrs = AttrsExpander::new(def).expand();
 
                 quote! {
-                    async fn #fn_name(&self, #(#params),*) -> Result<#res, #error_name>;
+                    #attrs
+                    #signature;
                 }
             })
             .collect()
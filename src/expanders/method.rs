@@ -1,76 +1,1114 @@
 use crate::{
     error::MacroResult,
-    input::{EndpointDef, HttpMethod},
+    input::{
+        BodyFormat, CursorSource, EndpointDef, HttpMethod, HttpProviderInput, JsonBackend, MapFn,
+        Paginate, ProviderOptions, ResFormat, SignBody,
+    },
 };
 use heck::ToSnakeCase;
 use proc_macro2::{Span, TokenStream};
 use quote::quote;
 use regex::Regex;
-use syn::Ident;
+use syn::{Ident, LitStr};
 
 const PATH_PARAM_REGEX: &str = r"\{([a-zA-Z0-9_]+)\}";
 
+/// Returns `T` when `ty` is syntactically `Option<T>`.
+pub fn option_inner(ty: &syn::Type) -> Option<&syn::Type> {
+    generic_inner(ty, "Option")
+}
+
+/// Returns `T` when `ty` is syntactically `Vec<T>`.
+pub fn vec_inner(ty: &syn::Type) -> Option<&syn::Type> {
+    generic_inner(ty, "Vec")
+}
+
+fn generic_inner<'t>(ty: &'t syn::Type, wrapper: &str) -> Option<&'t syn::Type> {
+    let syn::Type::Path(type_path) = ty else {
+        return None;
+    };
+    let segment = type_path.path.segments.last()?;
+    if segment.ident != wrapper {
+        return None;
+    }
+    let syn::PathArguments::AngleBracketed(ref args) = segment.arguments else {
+        return None;
+    };
+    match args.args.first()? {
+        syn::GenericArgument::Type(inner) if args.args.len() == 1 => Some(inner),
+        _ => None,
+    }
+}
+
+/// Returns an expression evaluating to the `reqwest::Method` of `method`.
+fn reqwest_method(method: &HttpMethod) -> TokenStream {
+    match method {
+        HttpMethod::GET => quote! { reqwest::Method::GET },
+        HttpMethod::POST => quote! { reqwest::Method::POST },
+        HttpMethod::PUT => quote! { reqwest::Method::PUT },
+        HttpMethod::DELETE => quote! { reqwest::Method::DELETE },
+        HttpMethod::CONNECT => quote! { reqwest::Method::CONNECT },
+        HttpMethod::TRACE => quote! { reqwest::Method::TRACE },
+        // `from_bytes` only rejects bytes outside the HTTP token characters,
+        // which parsing already rejected, so the `unwrap` cannot fail.
+        HttpMethod::Custom(verb) => {
+            let bytes = syn::LitByteStr::new(verb.value().as_bytes(), verb.span());
+            quote! { reqwest::Method::from_bytes(#bytes).unwrap() }
+        }
+    }
+}
+
+/// Returns the success type of an endpoint's generated methods, which is the
+/// `res_map` output when the response is transformed, paired with the
+/// `Location` header and then the elapsed time when those are requested.
+pub fn response_type(def: &EndpointDef, struct_name: &Ident) -> TokenStream {
+    let res = located_type(def, struct_name);
+    if def.measure {
+        quote! { (#res, std::time::Duration) }
+    } else {
+        res
+    }
+}
+
+/// Pairs the mapped response with its `Location` header when requested.
+fn located_type(def: &EndpointDef, struct_name: &Ident) -> TokenStream {
+    let res = mapped_type(def, struct_name);
+    if def.location {
+        quote! { (#res, Option<String>) }
+    } else {
+        res
+    }
+}
+
+/// Returns the type `res_map` maps the response into, which is the type of
+/// `res_format` unless the function declares its output.
+fn mapped_type(def: &EndpointDef, struct_name: &Ident) -> TokenStream {
+    match def.res_map {
+        Some(MapFn {
+            output: Some(ref output),
+            ..
+        }) => quote! { #output },
+        _ => format_type(def, struct_name),
+    }
+}
+
+/// Returns the type `res_format` turns the response into.
+fn format_type(def: &EndpointDef, struct_name: &Ident) -> TokenStream {
+    let body = def
+        .res
+        .as_ref()
+        .map(|t| quote! { #t })
+        .unwrap_or_else(|| quote! { () });
+    match def.res_format {
+        ResFormat::Json | ResFormat::Auto => body,
+        ResFormat::Status => quote! { reqwest::StatusCode },
+        ResFormat::Traced => {
+            let traced_response = traced_response_name(struct_name);
+            quote! { #traced_response<#body> }
+        }
+        ResFormat::JsonOrText => {
+            let json_or_text = json_or_text_name(struct_name);
+            quote! { #json_or_text<#body> }
+        }
+        ResFormat::WithStatus => quote! { (#body, reqwest::StatusCode) },
+        ResFormat::WithBytes => quote! { (#body, bytes::Bytes) },
+        ResFormat::Sse => {
+            let sse_stream = sse_stream_name(struct_name);
+            quote! { #sse_stream }
+        }
+        ResFormat::ToWriter => quote! { Vec<u8> },
+        ResFormat::Headers => quote! { reqwest::header::HeaderMap },
+    }
+}
+
+/// The error type an endpoint's methods return, which is the generated error
+/// unless `map_err` converts it to another type.
+pub fn call_error_type(def: &EndpointDef, error_name: &Ident) -> TokenStream {
+    match def.map_err {
+        Some(MapFn {
+            output: Some(ref error),
+            ..
+        }) => quote! { #error },
+        _ => quote! { #error_name },
+    }
+}
+
+/// The error type of the trait methods, which is the `E` parameter of the trait
+/// with `generic_error` and the generated error otherwise.
+pub fn trait_error_type(input: &HttpProviderInput, error_name: &Ident) -> Ident {
+    if input.options.generic_error {
+        Ident::new("E", Span::call_site())
+    } else {
+        error_name.clone()
+    }
+}
+
+/// Appends the endpoint's `query_defaults` whose keys the built `request` does
+/// not already have in its query string.
+fn expand_query_defaults(def: &EndpointDef) -> TokenStream {
+    if def.query_defaults.is_empty() {
+        return quote! {};
+    }
+
+    let keys = def.query_defaults.iter().map(|(key, _)| key);
+    let values = def.query_defaults.iter().map(|(_, value)| value);
+    quote! {
+        for (key, value) in [#((#keys, #values)),*] {
+            if !request.url().query_pairs().any(|(name, _)| name == key) {
+                request.url_mut().query_pairs_mut().append_pair(key, value);
+            }
+        }
+    }
+}
+
+/// Name of the struct of an entry in a GraphQL response's `errors` array.
+pub fn graphql_error_name(struct_name: &Ident) -> Ident {
+    Ident::new(&format!("{}GraphQlError", struct_name), struct_name.span())
+}
+
+/// Name of the enum returned by `res_format: json_or_text` endpoints.
+pub fn json_or_text_name(struct_name: &Ident) -> Ident {
+    Ident::new(&format!("{}JsonOrText", struct_name), struct_name.span())
+}
+
+/// Name of the struct of an event yielded by `res_format: sse` endpoints.
+pub fn sse_event_name(struct_name: &Ident) -> Ident {
+    Ident::new(&format!("{}SseEvent", struct_name), struct_name.span())
+}
+
+/// Name of the incremental `text/event-stream` parser behind `res_format: sse`.
+pub fn sse_parser_name(struct_name: &Ident) -> Ident {
+    Ident::new(&format!("{}SseParser", struct_name), struct_name.span())
+}
+
+/// Name of the boxed event stream returned by `res_format: sse` endpoints.
+pub fn sse_stream_name(struct_name: &Ident) -> Ident {
+    Ident::new(&format!("{}SseStream", struct_name), struct_name.span())
+}
+
+/// Name of the struct pairing a `res_format: traced` body with request metadata.
+pub fn traced_response_name(struct_name: &Ident) -> Ident {
+    Ident::new(
+        &format!("{}TracedResponse", struct_name),
+        struct_name.span(),
+    )
+}
+
+/// Returns the names of the `{param}` placeholders in a path template.
+pub fn path_param_names(path: &str) -> Vec<String> {
+    let re = Regex::new(PATH_PARAM_REGEX).expect("Invalid regex");
+    re.captures_iter(path)
+        .map(|cap| cap[1].to_string())
+        .collect()
+}
+
+/// Name of the enum callers use to pick a body format at runtime.
+pub fn body_format_name(struct_name: &Ident) -> Ident {
+    Ident::new(&format!("{}BodyFormat", struct_name), struct_name.span())
+}
+
+/// Whether the endpoint's URL is the same on every call against the
+/// provider's base URL, so it is joined once when the provider is built.
+pub fn has_static_url(def: &EndpointDef) -> bool {
+    def.path.is_some() && def.path_params.is_none()
+}
+
+/// Which reqwest client a generated provider is built on.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum ClientFlavor {
+    Async,
+    Blocking,
+}
+
+impl ClientFlavor {
+    /// Path of the reqwest module holding this flavor's client types.
+    pub fn module(self) -> TokenStream {
+        match self {
+            ClientFlavor::Async => quote! { reqwest },
+            ClientFlavor::Blocking => quote! { reqwest::blocking },
+        }
+    }
+
+    /// Suffix that resolves a client call, `.await` for the async client.
+    pub fn awaited(self) -> TokenStream {
+        match self {
+            ClientFlavor::Async => quote! { .await },
+            ClientFlavor::Blocking => quote! {},
+        }
+    }
+
+    /// Runs `body` as a block of its own, binding what it returns or `?`s to
+    /// `result`, so wrappers can act on the outcome of the whole body.
+    pub fn wrap_result(
+        self,
+        body: &TokenStream,
+        res: &TokenStream,
+        error_name: &Ident,
+    ) -> TokenStream {
+        match self {
+            ClientFlavor::Async => quote! {
+                let result: Result<#res, #error_name> = async { #body }.await;
+            },
+            ClientFlavor::Blocking => quote! {
+                let result = (|| -> Result<#res, #error_name> { #body })();
+            },
+        }
+    }
+}
+
+/// Per-call values a generated method body is parameterized on.
+///
+/// The trait methods use the provider's own base URL and timeout, while the
+/// inherent variants substitute the arguments supplied by the caller.
+pub struct CallContext {
+    pub base: TokenStream,
+    pub timeout: TokenStream,
+    /// Whether an `extra: &[(&str, &str)]` argument adds ad-hoc query pairs.
+    pub extra_query: bool,
+    /// Whether the call fetches a single page of a paginated endpoint, sending
+    /// the `cursor: Option<&str>` argument and returning the next cursor.
+    pub page: bool,
+    /// Whether the call streams the body into a `writer: &mut W` argument and
+    /// returns the number of bytes written.
+    pub writer: bool,
+    /// Whether the call is against the provider's own base URL, so a static
+    /// endpoint URL can be taken from the ones joined when it was built.
+    pub own_base: bool,
+}
+
+impl Default for CallContext {
+    fn default() -> Self {
+        Self {
+            base: quote! { self.url },
+            timeout: quote! { self.timeout },
+            extra_query: false,
+            page: false,
+            writer: false,
+            own_base: true,
+        }
+    }
+}
+
 pub struct MethodExpander<'a> {
     def: &'a EndpointDef,
+    input: &'a HttpProviderInput,
     error_name: &'a Ident,
+    flavor: ClientFlavor,
 }
 
 impl<'a> MethodExpander<'a> {
-    pub fn new(def: &'a EndpointDef, error_name: &'a Ident) -> Self {
-        Self { def, error_name }
+    pub fn new(
+        def: &'a EndpointDef,
+        input: &'a HttpProviderInput,
+        error_name: &'a Ident,
+        flavor: ClientFlavor,
+    ) -> Self {
+        Self {
+            def,
+            input,
+            error_name,
+            flavor,
+        }
     }
 
     pub fn expand(&self) -> MacroResult<TokenStream> {
-        let fn_name = FnNameExpander::new(self.def).expand();
-        let params = ParamsExpander::new(self.def).expand();
-        let res = self
-            .def
-            .res
-            .as_ref()
-            .map(|t| quote! { #t })
-            .unwrap_or_else(|| quote! { () });
-        let error_name = self.error_name;
+        let cfg = AttrsExpander::new(self.def).expand_cfg();
+        let trait_error = trait_error_type(self.input, self.error_name);
+        let signature =
+            SignatureExpander::new(self.def, self.input, &trait_error, self.flavor).expand();
+        let ctx = self.default_context();
+        let body = self.expand_body(&ctx);
+        let body = self.expand_generic_error(body, &self.result_type(&ctx));
 
-        let url_construction = UrlExpander::new(self.def, self.error_name).expand();
-        let request_builder = RequestExpander::new(self.def).expand();
-        let response_handler =
-            ResponseExpander::new(self.def.res.as_ref(), self.error_name).expand();
+        if self.input.options.boxed_futures && self.flavor == ClientFlavor::Async {
+            return Ok(quote! {
+                #cfg
+                #signature {
+                    Box::pin(async move { #body })
+                }
+            });
+        }
 
         Ok(quote! {
-            async fn #fn_name(&self, #(#params),*) -> Result<#res, #error_name> {
+            #cfg
+            #signature {
+                #body
+            }
+        })
+    }
+
+    /// Expands the inherent per-call variants of the endpoint:
+    /// * `*_timeout` takes the request timeout as a trailing argument
+    /// * `*_at` takes the base URL to join the endpoint path against
+    /// * `*_with_query` takes extra query pairs on top of `query_params`
+    /// * `*_body` returns the JSON that would be sent for a request body
+    /// * `*_page` and `*_all` fetch one page or stream every page of a
+    ///   paginated endpoint
+    /// * `*_to` streams the body of a `to_writer` endpoint into a writer
+    /// * `*_request` builds the request without sending it
+    /// * `*_builder` sets up the request builder without building it
+    pub fn expand_variants(&self) -> MacroResult<TokenStream> {
+        let params = ParamsExpander::new(self.def, self.input).expand();
+
+        let mut timeout_params = params.clone();
+        timeout_params.push(quote! { timeout: std::time::Duration });
+        let timeout_variant = self.expand_variant(
+            "timeout",
+            &timeout_params,
+            &CallContext {
+                timeout: quote! { timeout },
+                ..self.default_context()
+            },
+        );
+
+        let mut at_params = vec![quote! { base: &reqwest::Url }];
+        at_params.extend(params.clone());
+        let at_variant = self.expand_variant(
+            "at",
+            &at_params,
+            &CallContext {
+                base: quote! { base },
+                own_base: false,
+                ..self.default_context()
+            },
+        );
+
+        let query_variant = if self.def.query_params.is_some() {
+            let mut query_params = params;
+            query_params.push(quote! { extra: &[(&str, &str)] });
+            self.expand_variant(
+                "with_query",
+                &query_params,
+                &CallContext {
+                    extra_query: true,
+                    ..self.default_context()
+                },
+            )
+        } else {
+            quote! {}
+        };
+
+        let body_preview = self.expand_body_preview();
+        let pagination = self.expand_pagination();
+        let writer = self.expand_writer();
+        let request = self.expand_request_variant();
+        let builder = self.expand_builder_variant();
+
+        Ok(quote! {
+            #timeout_variant
+            #at_variant
+            #query_variant
+            #body_preview
+            #pagination
+            #writer
+            #request
+            #builder
+        })
+    }
+
+    /// Expands `*_page`, fetching the page at a cursor, and `*_all`, a stream
+    /// of every page that follows the cursors until one has no next cursor.
+    /// Streams are async, so blocking providers only get `*_page`.
+    fn expand_pagination(&self) -> TokenStream {
+        if self.def.paginate.is_none() {
+            return quote! {};
+        }
+
+        let params_expander = ParamsExpander::new(self.def, self.input);
+        let mut page_params = params_expander.expand();
+        page_params.push(quote! { cursor: Option<&str> });
+        let page_variant = self.expand_variant(
+            "page",
+            &page_params,
+            &CallContext {
+                page: true,
+                ..self.default_context()
+            },
+        );
+        if self.flavor == ClientFlavor::Blocking {
+            return page_variant;
+        }
+
+        let fn_name = FnNameExpander::new(self.def, &self.input.options).expand();
+        let page_fn = Ident::new(&format!("{}_page", fn_name), fn_name.span());
+        let all_fn = Ident::new(&format!("{}_all", fn_name), fn_name.span());
+        let lifetime = syn::Lifetime::new("'page", Span::call_site());
+        let params = params_expander.expand_with_lifetime(&lifetime);
+        let args = params_expander.expand_names();
+        let res = response_type(self.def, &self.input.struct_name);
+        let error_name = call_error_type(self.def, self.error_name);
+        let attrs = AttrsExpander::new(self.def).expand();
+
+        quote! {
+            #page_variant
+
+            #attrs
+            pub fn #all_fn<#lifetime>(
+                &#lifetime self,
+                #(#params),*
+            ) -> impl futures::Stream<Item = Result<#res, #error_name>> + #lifetime {
+                // The state is the cursor of the next page to fetch, or `None`
+                // once the last page was returned.
+                futures::stream::try_unfold(Some(None::<String>), move |cursor| {
+                    #(let #args = Clone::clone(&#args);)*
+                    async move {
+                        let Some(cursor) = cursor else {
+                            return Ok::<_, #error_name>(None);
+                        };
+                        #[allow(deprecated)]
+                        let (page, next_cursor) =
+                            self.#page_fn(#(#args,)* cursor.as_deref()).await?;
+                        Ok(Some((page, next_cursor.map(Some))))
+                    }
+                })
+            }
+        }
+    }
+
+    /// Expands `*_to`, copying the body into the writer chunk by chunk as it
+    /// arrives rather than buffering it.
+    fn expand_writer(&self) -> TokenStream {
+        if self.def.res_format != ResFormat::ToWriter {
+            return quote! {};
+        }
+
+        let fn_name = FnNameExpander::new(self.def, &self.input.options).expand();
+        let fn_name = Ident::new(&format!("{}_to", fn_name), fn_name.span());
+        let ctx = CallContext {
+            writer: true,
+            ..self.default_context()
+        };
+        let mut params = ParamsExpander::new(self.def, self.input).expand();
+        params.push(quote! { writer: &mut W });
+        let res = self.result_type(&ctx);
+        let error_name = call_error_type(self.def, self.error_name);
+        let attrs = AttrsExpander::new(self.def).expand();
+        let body = self.expand_body(&ctx);
+        let asyncness = match self.flavor {
+            ClientFlavor::Async => quote! { async },
+            ClientFlavor::Blocking => quote! {},
+        };
+
+        quote! {
+            #attrs
+            pub #asyncness fn #fn_name<W: std::io::Write + ?Sized>(
+                &self,
+                #(#params),*
+            ) -> Result<#res, #error_name> {
+                #body
+            }
+        }
+    }
+
+    /// Expands `*_request`, building the request the endpoint would send,
+    /// including the interceptor's changes, without sending it.
+    fn expand_request_variant(&self) -> TokenStream {
+        let fn_name = FnNameExpander::new(self.def, &self.input.options).expand();
+        let builder_fn = Ident::new(&format!("{}_builder", fn_name), fn_name.span());
+        let fn_name = Ident::new(&format!("{}_request", fn_name), fn_name.span());
+        let params_expander = ParamsExpander::new(self.def, self.input).without_call_bounds();
+        let params = params_expander.expand();
+        let args = params_expander.expand_names();
+        let module = self.flavor.module();
+        let error_name = self.error_name;
+        let attrs = AttrsExpander::new(self.def).expand();
+
+        quote! {
+            #attrs
+            pub fn #fn_name(&self, #(#params),*) -> Result<#module::Request, #error_name> {
+                #[allow(deprecated)]
+                let builder = self.#builder_fn(#(#args),*)?;
+                builder.build().map_err(#error_name::from)
+            }
+        }
+    }
+
+    /// Expands `*_builder`, setting up the request builder the endpoint would
+    /// send, so callers can chain onto it before sending it themselves. The
+    /// request is built once to apply the `query_defaults` and the
+    /// interceptor, then turned back into a builder.
+    fn expand_builder_variant(&self) -> TokenStream {
+        let fn_name = FnNameExpander::new(self.def, &self.input.options).expand();
+        let fn_name = Ident::new(&format!("{}_builder", fn_name), fn_name.span());
+        let ctx = self.default_context();
+        let params = ParamsExpander::new(self.def, self.input)
+            .without_call_bounds()
+            .expand();
+        let validation = self.expand_validation();
+        let url_construction = self.url_expander(&ctx).expand();
+        let request_builder = RequestExpander::new(self.def, self.error_name, &ctx, self.flavor)
+            .with_request_id(self.sends_request_id())
+            .with_correlation_id(self.input.options.correlation_id.as_ref())
+            .expand();
+        let query_defaults = expand_query_defaults(self.def);
+        let into_builder = self.expand_into_builder();
+        let module = self.flavor.module();
+        let error_name = self.error_name;
+        let attrs = AttrsExpander::new(self.def).expand();
+
+        quote! {
+            #attrs
+            pub fn #fn_name(
+                &self,
+                #(#params),*
+            ) -> Result<#module::RequestBuilder, #error_name> {
+                #validation
                 #url_construction
                 #request_builder
-                #response_handler
+                let mut request = request.build().map_err(#error_name::from)?;
+                #query_defaults
+                if let Some(ref interceptor) = self.interceptor {
+                    request = interceptor(request);
+                }
+                Ok(#into_builder)
             }
-        })
+        }
+    }
+
+    /// Turns the built `request` back into a builder on the provider's client.
+    /// The blocking builder has no `from_parts`, so its parts are copied over.
+    fn expand_into_builder(&self) -> TokenStream {
+        match self.flavor {
+            ClientFlavor::Async => quote! {
+                reqwest::RequestBuilder::from_parts(self.client.clone(), request)
+            },
+            ClientFlavor::Blocking => quote! {
+                {
+                    let mut builder = self
+                        .client
+                        .request(request.method().clone(), request.url().clone())
+                        .headers(request.headers().clone())
+                        .version(request.version());
+                    if let Some(timeout) = request.timeout() {
+                        builder = builder.timeout(*timeout);
+                    }
+                    if let Some(body) = request.body_mut().take() {
+                        builder = builder.body(body);
+                    }
+                    builder
+                }
+            },
+        }
+    }
+
+    /// Expands the associated `*_method` function returning the endpoint's
+    /// `reqwest::Method`. Like the other generated methods it is `#[must_use]`,
+    /// which here is the only lint on a dropped result, as `reqwest::Method`
+    /// is not `#[must_use]` itself:
+    ///
+    /// ```compile_fail
+    /// #![deny(unused_must_use)]
+    /// use http_provider_macro::http_provider;
+    ///
+    /// http_provider!(
+    ///     UserApi,
+    ///     {
+    ///         {
+    ///             path: "/users",
+    ///             method: GET,
+    ///             res_format: status,
+    ///         },
+    ///     }
+    /// );
+    ///
+    /// fn main() {
+    ///     UserApi::get_users_method();
+    /// }
+    /// ```
+    pub fn expand_method_accessor(&self) -> TokenStream {
+        let fn_name = FnNameExpander::new(self.def, &self.input.options).expand();
+        let fn_name = Ident::new(&format!("{}_method", fn_name), fn_name.span());
+        let method = reqwest_method(&self.def.method);
+        let cfg = AttrsExpander::new(self.def).expand_cfg();
+        quote! {
+            /// The HTTP method the endpoint is called with.
+            #[must_use]
+            #cfg
+            pub fn #fn_name() -> reqwest::Method {
+                #method
+            }
+        }
+    }
+
+    /// Serializes a request body the way the endpoint would send it, without
+    /// making a request. Only JSON bodies have a preview.
+    fn expand_body_preview(&self) -> TokenStream {
+        let Some(ref req) = self.def.req else {
+            return quote! {};
+        };
+        if self.def.body_format != BodyFormat::Json || !self.def.runtime_body_formats.is_empty() {
+            return quote! {};
+        }
+
+        let fn_name = FnNameExpander::new(self.def, &self.input.options).expand();
+        let fn_name = Ident::new(&format!("{}_body", fn_name), fn_name.span());
+        let error_name = self.error_name;
+        let cfg = AttrsExpander::new(self.def).expand_cfg();
+        quote! {
+            #cfg
+            pub fn #fn_name(&self, body: &#req) -> Result<String, #error_name> {
+                serde_json::to_string(body).map_err(|e| #error_name::Serialization(e.to_string()))
+            }
+        }
+    }
+
+    /// The context of a call against the endpoint's own base URL, which is its
+    /// `host` when declared and the provider's URL otherwise, with its own
+    /// `timeout` when declared.
+    fn default_context(&self) -> CallContext {
+        let mut ctx = CallContext::default();
+        if let Some(timeout) = self.def.timeout {
+            ctx.timeout = quote! { std::time::Duration::from_millis(#timeout) };
+        }
+        if let Some(mul) = self.def.timeout_mul {
+            ctx.timeout = quote! { self.timeout * #mul };
+        }
+        let Some(ref host) = self.def.host else {
+            return ctx;
+        };
+
+        // The host was parsed when the macro expanded, so parsing it again
+        // cannot fail; it is parsed once and kept for later calls.
+        CallContext {
+            base: quote! {
+                {
+                    static HOST: std::sync::OnceLock<reqwest::Url> = std::sync::OnceLock::new();
+                    HOST.get_or_init(|| {
+                        reqwest::Url::parse(#host).expect("host is validated by the macro")
+                    })
+                }
+            },
+            ..ctx
+        }
+    }
+
+    /// The URL construction of a call, taking the URL of a static endpoint from
+    /// the ones joined when the provider was built.
+    fn url_expander<'c>(&'c self, ctx: &'c CallContext) -> UrlExpander<'c> {
+        let cached = if ctx.own_base {
+            self.input
+                .endpoints
+                .iter()
+                .filter(|def| has_static_url(def))
+                .position(|def| std::ptr::eq(def, self.def))
+        } else {
+            None
+        };
+        UrlExpander::new(self.def, self.error_name, &ctx.base).with_cached(cached)
+    }
+
+    fn expand_variant(
+        &self,
+        suffix: &str,
+        params: &[TokenStream],
+        ctx: &CallContext,
+    ) -> TokenStream {
+        let fn_name = FnNameExpander::new(self.def, &self.input.options).expand();
+        let fn_name = Ident::new(&format!("{}_{}", fn_name, suffix), fn_name.span());
+        let res = self.result_type(ctx);
+        let error_name = call_error_type(self.def, self.error_name);
+        let attrs = AttrsExpander::new(self.def).expand();
+        let body = self.expand_body(ctx);
+        let asyncness = match self.flavor {
+            ClientFlavor::Async => quote! { async },
+            ClientFlavor::Blocking => quote! {},
+        };
+
+        quote! {
+            #attrs
+            pub #asyncness fn #fn_name(&self, #(#params),*) -> Result<#res, #error_name> {
+                #body
+            }
+        }
+    }
+
+    fn expand_body(&self, ctx: &CallContext) -> TokenStream {
+        let validation = self.expand_validation();
+        let request_logging = self.expand_request_logging();
+        let url_construction = self.url_expander(ctx).expand();
+        let request_builder = RequestExpander::new(self.def, self.error_name, ctx, self.flavor)
+            .with_request_id(self.sends_request_id())
+            .with_correlation_id(self.input.options.correlation_id.as_ref())
+            .expand();
+        let response_handler =
+            ResponseExpander::new(self.def, self.input, self.error_name, self.flavor)
+                .paged(ctx.page)
+                .writing(ctx.writer)
+                .expand();
+        let res = self.result_type(ctx);
+
+        let rate_limit = if self.input.options.rate_limit.is_some() {
+            quote! { self.rate_limiter.until_ready().await; }
+        } else {
+            quote! {}
+        };
+
+        let body = quote! {
+            #validation
+            #request_logging
+            #url_construction
+            #request_builder
+            #rate_limit
+            #response_handler
+        };
+
+        let body = self.expand_total_timeout(body, &res);
+        let body = self.expand_deadline(body, &res);
+        let body = self.expand_cancellation(body, &res);
+        let body = self.expand_instrumentation(body, &res);
+        self.expand_map_err(body, &res)
+    }
+
+    /// Passes the error of the call through the endpoint's `map_err` function.
+    fn expand_map_err(&self, body: TokenStream, res: &TokenStream) -> TokenStream {
+        let Some(ref map_err) = self.def.map_err else {
+            return body;
+        };
+
+        let with = &map_err.with;
+        let result = self.flavor.wrap_result(&body, res, self.error_name);
+        quote! {
+            #result
+            result.map_err(#with)
+        }
+    }
+
+    /// Converts the error of a trait method into the trait's error parameter.
+    fn expand_generic_error(&self, body: TokenStream, res: &TokenStream) -> TokenStream {
+        if !self.input.options.generic_error {
+            return body;
+        }
+
+        let result = self.flavor.wrap_result(&body, res, self.error_name);
+        quote! {
+            #result
+            result.map_err(E::from)
+        }
+    }
+
+    /// Whether requests carry an `X-Request-Id`, as set on the endpoint or
+    /// else on the provider.
+    fn sends_request_id(&self) -> bool {
+        self.def.request_id.unwrap_or(self.input.options.request_id)
+    }
+
+    /// The success type of a call in `ctx`, adding the next cursor to pages.
+    fn result_type(&self, ctx: &CallContext) -> TokenStream {
+        let res = response_type(self.def, &self.input.struct_name);
+        if ctx.writer {
+            quote! { u64 }
+        } else if ctx.page {
+            quote! { (#res, Option<String>) }
+        } else {
+            res
+        }
+    }
+
+    /// Bounds the whole call by the caller's deadline, if enabled. The
+    /// per-request timeout still applies when the deadline is later.
+    fn expand_deadline(&self, body: TokenStream, res: &TokenStream) -> TokenStream {
+        if !self.input.options.deadline {
+            return body;
+        }
+
+        let error_name = self.error_name;
+        quote! {
+            let call = async { #body };
+            let result: Result<#res, #error_name> = match deadline {
+                Some(deadline) => tokio::time::timeout_at(deadline, call)
+                    .await
+                    .unwrap_or(Err(#error_name::DeadlineExceeded)),
+                None => call.await,
+            };
+            result
+        }
+    }
+
+    /// Bounds the whole call, every retry included, by the provider's
+    /// `total_timeout_ms`.
+    fn expand_total_timeout(&self, body: TokenStream, res: &TokenStream) -> TokenStream {
+        let Some(ms) = self.input.options.total_timeout_ms else {
+            return body;
+        };
+
+        let error_name = self.error_name;
+        quote! {
+            let call = async { #body };
+            let result: Result<#res, #error_name> =
+                tokio::time::timeout(std::time::Duration::from_millis(#ms), call)
+                    .await
+                    .unwrap_or(Err(#error_name::DeadlineExceeded));
+            result
+        }
+    }
+
+    /// Aborts the call once the caller's token is cancelled, if enabled.
+    fn expand_cancellation(&self, body: TokenStream, res: &TokenStream) -> TokenStream {
+        if !self.input.options.cancellation {
+            return body;
+        }
+
+        let error_name = self.error_name;
+        quote! {
+            let call = async { #body };
+            let result: Result<#res, #error_name> = match cancel {
+                Some(cancel) => tokio::select! {
+                    biased;
+                    _ = cancel.cancelled() => Err(#error_name::Cancelled),
+                    result = call => result,
+                },
+                None => call.await,
+            };
+            result
+        }
+    }
+
+    /// Wraps the method body with per-call instrumentation, if enabled.
+    fn expand_instrumentation(&self, body: TokenStream, res: &TokenStream) -> TokenStream {
+        if !self.input.options.metrics {
+            return body;
+        }
+
+        let method = self.def.method.verb();
+        let path = self
+            .def
+            .path
+            .as_ref()
+            .map_or_else(|| "/".to_string(), |path| path.value());
+
+        let result = self.flavor.wrap_result(&body, res, self.error_name);
+
+        quote! {
+            let started = std::time::Instant::now();
+            #result
+            let outcome = if result.is_ok() { "success" } else { "error" };
+            metrics::counter!(
+                "http_provider_requests_total",
+                "method" => #method,
+                "path" => #path,
+                "outcome" => outcome,
+            )
+            .increment(1);
+            metrics::histogram!(
+                "http_provider_request_duration_seconds",
+                "method" => #method,
+                "path" => #path,
+                "outcome" => outcome,
+            )
+            .record(started.elapsed().as_secs_f64());
+            result
+        }
+    }
+
+    /// Logs the request body at trace level when `log_bodies` is enabled. Only
+    /// JSON bodies are logged, and only serialized when the level is enabled.
+    fn expand_request_logging(&self) -> TokenStream {
+        if !self.input.options.log_bodies || self.def.req.is_none() {
+            return quote! {};
+        }
+
+        let fn_name = FnNameExpander::new(self.def, &self.input.options)
+            .expand()
+            .to_string();
+        let body = if self.def.owns_body() {
+            quote! { &body }
+        } else {
+            quote! { body }
+        };
+        let serialized = match self.def.body_format {
+            _ if !self.def.runtime_body_formats.is_empty() => return quote! {},
+            BodyFormat::Json => quote! { serde_json::to_string(#body) },
+            // Raw and streamed bodies are opaque, so only serialized JSON is logged.
+            BodyFormat::RawJson | BodyFormat::Stream | BodyFormat::Form | BodyFormat::Multipart => {
+                return quote! {}
+            }
+        };
+        quote! {
+            if tracing::enabled!(tracing::Level::TRACE) {
+                if let Ok(request_body) = #serialized {
+                    tracing::trace!(endpoint = #fn_name, %request_body, "sending request body");
+                }
+            }
+        }
+    }
+
+    fn expand_validation(&self) -> TokenStream {
+        let Some(ref validate_with) = self.def.validate_with else {
+            return quote! {};
+        };
+
+        let error_name = self.error_name;
+        let body = if self.def.owns_body() {
+            quote! { &body }
+        } else {
+            quote! { body }
+        };
+        quote! {
+            #validate_with(#body).map_err(|e| #error_name::Validation(e.to_string()))?;
+        }
     }
 }
 
-pub struct FnNameExpander<'a> {
+/// Expands the attributes shared by an endpoint's trait declaration and its
+/// inherent variants.
+pub struct AttrsExpander<'a> {
     def: &'a EndpointDef,
 }
 
-impl<'a> FnNameExpander<'a> {
+impl<'a> AttrsExpander<'a> {
     pub fn new(def: &'a EndpointDef) -> Self {
         Self { def }
     }
 
+    /// Expands the attributes of the trait declaration and the inherent
+    /// variants. Arguments grow with every input an endpoint declares, so the
+    /// argument count is not linted.
+    pub fn expand(&self) -> TokenStream {
+        let mut attrs = vec![
+            quote! { #[must_use] },
+            quote! { #[allow(clippy::too_many_arguments)] },
+            self.expand_cfg(),
+        ];
+
+        if let Some(ref note) = self.def.deprecated {
+            attrs.push(quote! { #[deprecated(note = #note)] });
+        }
+
+        quote! { #(#attrs)* }
+    }
+
+    /// Expands the endpoint's `#[cfg]`, for every item generated per endpoint.
+    pub fn expand_cfg(&self) -> TokenStream {
+        match self.def.cfg {
+            Some(ref cfg) => quote! { #[cfg(#cfg)] },
+            None => quote! {},
+        }
+    }
+}
+
+/// Expands the signature shared by an endpoint's trait declaration and its
+/// trait implementation.
+///
+/// With `boxed_futures` the method returns a pinned, boxed `Send` future
+/// instead of being an `async fn`, which keeps the trait object-safe. All
+/// borrowed parameters then share the future's lifetime. The blocking flavor
+/// always expands to a plain `fn`.
+pub struct SignatureExpander<'a> {
+    def: &'a EndpointDef,
+    input: &'a HttpProviderInput,
+    error_name: &'a Ident,
+    flavor: ClientFlavor,
+}
+
+impl<'a> SignatureExpander<'a> {
+    pub fn new(
+        def: &'a EndpointDef,
+        input: &'a HttpProviderInput,
+        error_name: &'a Ident,
+        flavor: ClientFlavor,
+    ) -> Self {
+        Self {
+            def,
+            input,
+            error_name,
+            flavor,
+        }
+    }
+
+    pub fn expand(&self) -> TokenStream {
+        let fn_name = FnNameExpander::new(self.def, &self.input.options).expand();
+        let params = ParamsExpander::new(self.def, self.input);
+        let res = response_type(self.def, &self.input.struct_name);
+        let error_name = call_error_type(self.def, self.error_name);
+
+        if self.flavor == ClientFlavor::Blocking {
+            let params = params.expand();
+            return quote! {
+                fn #fn_name(&self, #(#params),*) -> Result<#res, #error_name>
+            };
+        }
+
+        if self.input.options.boxed_futures {
+            let lifetime = syn::Lifetime::new("'fut", Span::call_site());
+            let params = params.expand_with_lifetime(&lifetime);
+            return quote! {
+                fn #fn_name<#lifetime>(&#lifetime self, #(#params),*) -> std::pin::Pin<
+                    Box<dyn std::future::Future<Output = Result<#res, #error_name>> + Send + #lifetime>,
+                >
+            };
+        }
+
+        let params = params.expand();
+        quote! {
+            async fn #fn_name(&self, #(#params),*) -> Result<#res, #error_name>
+        }
+    }
+}
+
+/// Names of every inherent and trait method generated for the endpoint, which
+/// must not collide with those of other endpoints or of the provider itself.
+pub fn generated_method_names(def: &EndpointDef, options: &ProviderOptions) -> Vec<String> {
+    let fn_name = FnNameExpander::new(def, options).expand().to_string();
+    let mut suffixes = vec!["request", "timeout", "at", "method", "builder"];
+    if def.query_params.is_some() {
+        suffixes.push("with_query");
+    }
+    if def.req.is_some()
+        && def.body_format == BodyFormat::Json
+        && def.runtime_body_formats.is_empty()
+    {
+        suffixes.push("body");
+    }
+    if def.paginate.is_some() {
+        suffixes.extend(["page", "all"]);
+    }
+    if def.res_format == ResFormat::ToWriter {
+        suffixes.push("to");
+    }
+
+    let mut names = vec![fn_name.clone()];
+    names.extend(
+        suffixes
+            .into_iter()
+            .map(|suffix| format!("{}_{}", fn_name, suffix)),
+    );
+    names
+}
+
+pub struct FnNameExpander<'a> {
+    def: &'a EndpointDef,
+    options: &'a ProviderOptions,
+}
+
+impl<'a> FnNameExpander<'a> {
+    pub fn new(def: &'a EndpointDef, options: &'a ProviderOptions) -> Self {
+        Self { def, options }
+    }
+
+    /// Expands the method name, wrapped in the provider's `fn_prefix` and
+    /// `fn_suffix`, which also apply to explicit `fn_name`s.
     pub fn expand(&self) -> Ident {
+        let name = self.expand_unaffixed();
+        let prefix = self.options.fn_prefix.as_ref().map(LitStr::value);
+        let suffix = self.options.fn_suffix.as_ref().map(LitStr::value);
+        Ident::new(
+            &format!(
+                "{}{}{}",
+                prefix.unwrap_or_default(),
+                name,
+                suffix.unwrap_or_default()
+            ),
+            name.span(),
+        )
+    }
+
+    fn expand_unaffixed(&self) -> Ident {
         if let Some(ref name) = self.def.fn_name {
             return name.clone();
         }
 
-        let method_str = self.def.method.as_str();
-        let name = if let Some(ref path) = self.def.path {
-            let path_str = path.value().trim_start_matches('/').to_string();
-            let path_part = self.expand_fn_name_with_path(&path_str);
-            format!("{}_{}", method_str, path_part).to_snake_case()
-        } else {
-            method_str.to_string()
-        };
-
         Ident::new(
-            &name,
+            &self.derived_name(),
             self.def
                 .path
                 .as_ref()
@@ -78,6 +1116,20 @@ impl<'a> FnNameExpander<'a> {
         )
     }
 
+    /// The name derived from the method and path when there is no `fn_name`.
+    /// It is not a valid identifier for every custom method, which parsing
+    /// checks before anything expands it.
+    pub fn derived_name(&self) -> String {
+        let method_str = self.def.method.name();
+        if let Some(ref path) = self.def.path {
+            let path_str = path.value().trim_start_matches('/').to_string();
+            let path_part = self.expand_fn_name_with_path(&path_str);
+            format!("{}_{}", method_str, path_part).to_snake_case()
+        } else {
+            method_str.to_snake_case()
+        }
+    }
+
     fn expand_fn_name_with_path(&self, path_str: &str) -> String {
         // Handle path parameters: extract them and format as by_{param1}_and_{param2}...
         if self.def.path_params.is_some() {
@@ -121,82 +1173,240 @@ impl<'a> FnNameExpander<'a> {
 
 pub struct ParamsExpander<'a> {
     def: &'a EndpointDef,
+    input: &'a HttpProviderInput,
+    call_bounds: bool,
 }
 
 impl<'a> ParamsExpander<'a> {
-    pub fn new(def: &'a EndpointDef) -> Self {
-        Self { def }
+    pub fn new(def: &'a EndpointDef, input: &'a HttpProviderInput) -> Self {
+        Self {
+            def,
+            input,
+            call_bounds: true,
+        }
+    }
+
+    /// Leaves out the `deadline` and `cancel` parameters, for methods that
+    /// send nothing.
+    pub fn without_call_bounds(mut self) -> Self {
+        self.call_bounds = false;
+        self
     }
 
     pub fn expand(&self) -> Vec<TokenStream> {
+        self.expand_with_reference(quote! { & })
+    }
+
+    /// Expands the parameters with every borrow tied to `lifetime`.
+    pub fn expand_with_lifetime(&self, lifetime: &syn::Lifetime) -> Vec<TokenStream> {
+        self.expand_with_reference(quote! { & #lifetime })
+    }
+
+    /// Expands the parameter names, in the same order as their declarations,
+    /// e.g. to forward a call.
+    pub fn expand_names(&self) -> Vec<Ident> {
+        self.expand_typed(quote! { & })
+            .into_iter()
+            .map(|(name, _)| name)
+            .collect()
+    }
+
+    fn expand_with_reference(&self, reference: TokenStream) -> Vec<TokenStream> {
+        self.expand_typed(reference)
+            .into_iter()
+            .map(|(name, ty)| quote! { #name: #ty })
+            .collect()
+    }
+
+    fn expand_typed(&self, reference: TokenStream) -> Vec<(Ident, TokenStream)> {
         let mut params = Vec::new();
+        let param = |name: &str| Ident::new(name, Span::call_site());
 
         if let Some(ref path_params) = self.def.path_params {
-            params.push(quote! { path_params: &#path_params });
+            params.push((param("path_params"), quote! { #reference #path_params }));
         }
         if let Some(ref body) = self.def.req {
-            params.push(quote! { body: &#body });
+            if self.def.owns_body() {
+                params.push((param("body"), quote! { #body }));
+            } else if let Some(op) = vec_inner(body).filter(|_| self.def.batch) {
+                params.push((param("body"), quote! { #reference [#op] }));
+            } else {
+                params.push((param("body"), quote! { #reference #body }));
+            }
+            if self.def.body_format == BodyFormat::Multipart {
+                params.push((param("file"), quote! { reqwest::multipart::Part }));
+            }
+            if !self.def.runtime_body_formats.is_empty() {
+                let body_format = body_format_name(&self.input.struct_name);
+                params.push((param("body_format"), quote! { #body_format }));
+            }
         }
         if let Some(ref query_params) = self.def.query_params {
-            params.push(quote! { query_params: &#query_params });
+            params.push((param("query_params"), quote! { #reference #query_params }));
+        }
+        if let Some(ref query_body) = self.def.query_body {
+            params.push((param("query_body"), quote! { #reference #query_body }));
+        }
+        for (name, ty) in &self.def.query_types {
+            params.push((name.clone(), quote! { #ty }));
         }
         if let Some(ref headers) = self.def.headers {
-            params.push(quote! { headers: #headers });
+            params.push((param("headers"), quote! { #headers }));
+        }
+        if self.def.if_match {
+            params.push((param("etag"), quote! { #reference str }));
+        }
+        if self.def.extra_headers {
+            params.push((
+                param("extra_headers"),
+                quote! { #reference [(reqwest::header::HeaderName, reqwest::header::HeaderValue)] },
+            ));
+        }
+        if self.input.options.deadline && self.call_bounds {
+            params.push((param("deadline"), quote! { Option<tokio::time::Instant> }));
+        }
+        if self.input.options.cancellation && self.call_bounds {
+            params.push((
+                param("cancel"),
+                quote! { Option<#reference tokio_util::sync::CancellationToken> },
+            ));
         }
 
         params
     }
 }
 
+/// The local holding the pairs of the inline `query_types` arguments. Its
+/// mixed-site span keeps it apart from an argument of the same name.
+fn query_pairs_ident() -> Ident {
+    Ident::new("query_pairs", Span::mixed_site())
+}
+
 pub struct UrlExpander<'a> {
     def: &'a EndpointDef,
     error_name: &'a Ident,
+    base: &'a TokenStream,
+    cached: Option<usize>,
 }
 
 impl<'a> UrlExpander<'a> {
-    pub fn new(def: &'a EndpointDef, error_name: &'a Ident) -> Self {
-        Self { def, error_name }
+    pub fn new(def: &'a EndpointDef, error_name: &'a Ident, base: &'a TokenStream) -> Self {
+        Self {
+            def,
+            error_name,
+            base,
+            cached: None,
+        }
+    }
+
+    /// Takes the URL from the provider's `static_urls` at `index` instead of
+    /// joining the path on every call.
+    pub fn with_cached(mut self, index: Option<usize>) -> Self {
+        self.cached = index;
+        self
     }
 
     pub fn expand(&self) -> TokenStream {
-        let Some(ref path) = self.def.path else {
-            return quote! { let url = self.url.clone(); };
+        let query_pairs = self.expand_query_pairs();
+        let base = self.base;
+        let url = match self.def.path {
+            None => quote! { let url = #base.clone(); },
+            Some(ref path) if self.def.path_params.is_some() => self.expand_with_path_params(path),
+            Some(ref path) => self.expand_without_path_params(path),
         };
+        quote! {
+            #query_pairs
+            #url
+        }
+    }
 
-        if self.def.path_params.is_some() {
-            self.expand_with_path_params(path)
-        } else {
-            self.expand_without_path_params(path)
+    /// Collects the inline query arguments into pairs, skipping `Option`
+    /// arguments that are `None`. This comes before any other local is bound,
+    /// so an argument named like one, e.g. `url`, is read rather than shadowed.
+    fn expand_query_pairs(&self) -> TokenStream {
+        if self.def.query_types.is_empty() {
+            return quote! {};
+        }
+
+        let query_pairs = query_pairs_ident();
+        let pushes = self.def.query_types.iter().map(|(name, ty)| {
+            let key = name.to_string();
+            if option_inner(ty).is_some() {
+                quote! {
+                    if let Some(ref value) = #name {
+                        #query_pairs.push((#key, value.to_string()));
+                    }
+                }
+            } else {
+                quote! { #query_pairs.push((#key, #name.to_string())); }
+            }
+        });
+        quote! {
+            let mut #query_pairs: Vec<(&str, String)> = Vec::new();
+            #(#pushes)*
         }
     }
 
+    /// Splits the path template into a `format!` string at expansion time, so
+    /// a call fills in every parameter in one allocation instead of copying the
+    /// path once per parameter.
     fn expand_with_path_params(&self, path: &syn::LitStr) -> TokenStream {
+        let error_name = self.error_name;
         let re = Regex::new(PATH_PARAM_REGEX).expect("Invalid regex");
         let path_str = path.value();
-        let replacements: Vec<_> = re
-            .captures_iter(&path_str)
-            .map(|cap| {
-                let param_name = &cap[1];
+        let escape = |literal: &str| literal.replace('{', "{{").replace('}', "}}");
+
+        let mut template = String::new();
+        let mut lookups = Vec::new();
+        let mut args = Vec::new();
+        let mut last = 0;
+        for cap in re.captures_iter(&path_str) {
+            let whole = cap.get(0).expect("capture 0 always matches");
+            template.push_str(&escape(&path_str[last..whole.start()]));
+            template.push_str("{}");
+            last = whole.end();
+
+            let param_name = &cap[1];
+            if self.def.path_params_map {
+                let value = Ident::new(&format!("value_{}", param_name), Span::call_site());
+                lookups.push(quote! {
+                    let #value = path_params.get(#param_name).ok_or_else(|| {
+                        #error_name::UrlConstruction(
+                            concat!("missing path parameter `", #param_name, "`").to_string(),
+                        )
+                    })?;
+                });
+                args.push(quote! { #value });
+            } else {
                 let ident = Ident::new(param_name, Span::call_site());
-                quote! {
-                    path = path.replace(concat!("{", #param_name, "}"), &path_params.#ident.to_string());
-                }
-            })
-            .collect();
+                args.push(quote! { path_params.#ident });
+            }
+        }
+        template.push_str(&escape(&path_str[last..]));
+        let template = syn::LitStr::new(&template, path.span());
 
-        let error_name = self.error_name;
+        let base = self.base;
         quote! {
-            let mut path = #path.to_string();
-            #(#replacements)*
-            let url = self.url.join(&path)
+            #(#lookups)*
+            let path = format!(#template, #(#args),*);
+            let url = #base.join(&path)
                 .map_err(|e| #error_name::UrlConstruction(e.to_string()))?;
         }
     }
 
     fn expand_without_path_params(&self, path: &syn::LitStr) -> TokenStream {
         let error_name = self.error_name;
+        if let Some(index) = self.cached {
+            return quote! {
+                let url = self.static_urls[#index]
+                    .clone()
+                    .map_err(#error_name::UrlConstruction)?;
+            };
+        }
+
+        let base = self.base;
         quote! {
-            let url = self.url.join(#path)
+            let url = #base.join(#path)
                 .map_err(|e| #error_name::UrlConstruction(e.to_string()))?;
         }
     }
@@ -204,19 +1414,50 @@ impl<'a> UrlExpander<'a> {
 
 pub struct RequestExpander<'a> {
     def: &'a EndpointDef,
+    error_name: &'a Ident,
+    ctx: &'a CallContext,
+    flavor: ClientFlavor,
+    request_id: bool,
+    correlation_id: Option<&'a syn::Path>,
 }
 
 impl<'a> RequestExpander<'a> {
-    pub fn new(def: &'a EndpointDef) -> Self {
-        Self { def }
+    pub fn new(
+        def: &'a EndpointDef,
+        error_name: &'a Ident,
+        ctx: &'a CallContext,
+        flavor: ClientFlavor,
+    ) -> Self {
+        Self {
+            def,
+            error_name,
+            ctx,
+            flavor,
+            request_id: false,
+            correlation_id: None,
+        }
+    }
+
+    /// Sends a fresh `X-Request-Id` UUID with the request when `request_id` is set.
+    pub fn with_request_id(mut self, request_id: bool) -> Self {
+        self.request_id = request_id;
+        self
+    }
+
+    /// Sends the value of the `correlation_id` task-local as an
+    /// `X-Correlation-Id` header when the request is built inside its scope.
+    pub fn with_correlation_id(mut self, correlation_id: Option<&'a syn::Path>) -> Self {
+        self.correlation_id = correlation_id;
+        self
     }
 
     pub fn expand(&self) -> TokenStream {
         let method_call = self.expand_method_call();
         let modifications = self.expand_modifications();
+        let timeout = &self.ctx.timeout;
 
         quote! {
-            let mut request = #method_call.timeout(self.timeout);
+            let mut request = #method_call.timeout(#timeout);
             #(#modifications)*
         }
     }
@@ -227,73 +1468,901 @@ impl<'a> RequestExpander<'a> {
             HttpMethod::POST => quote! { self.client.post(url) },
             HttpMethod::PUT => quote! { self.client.put(url) },
             HttpMethod::DELETE => quote! { self.client.delete(url) },
+            // The client has no shorthand for these, so they go through `request`
+            HttpMethod::CONNECT | HttpMethod::TRACE | HttpMethod::Custom(_) => {
+                let method = reqwest_method(&self.def.method);
+                quote! { self.client.request(#method, url) }
+            }
+        }
+    }
+
+    /// Serializes the JSON body up front so the HMAC-SHA256 signature covers
+    /// exactly the bytes that are sent.
+    fn expand_signed_body(&self, sign_body: &SignBody, body: &TokenStream) -> TokenStream {
+        let error_name = self.error_name;
+        let header = &sign_body.header;
+        let secret_field = &sign_body.secret_field;
+        quote! {
+            let bytes = serde_json::to_vec(#body)
+                .map_err(|e| #error_name::Signing(e.to_string()))?;
+            let secret = self.#secret_field.as_deref().ok_or_else(|| {
+                #error_name::Signing(format!("no secret set for the `{}` header", #header))
+            })?;
+            let mut mac = <hmac::Hmac<sha2::Sha256> as hmac::KeyInit>::new_from_slice(secret)
+                .map_err(|e| #error_name::Signing(e.to_string()))?;
+            hmac::Mac::update(&mut mac, &bytes);
+            let signature: String = hmac::Mac::finalize(mac)
+                .into_bytes()
+                .iter()
+                .map(|byte| format!("{:02x}", byte))
+                .collect();
+            request = request
+                .header(reqwest::header::CONTENT_TYPE, "application/json")
+                .header(#header, signature)
+                .body(bytes);
         }
     }
 
     fn expand_modifications(&self) -> Vec<TokenStream> {
         let mut modifications = Vec::new();
+        // Serialized bodies are encoded from a reference even when owned.
+        let body = if self.def.own_body {
+            quote! { &body }
+        } else {
+            quote! { body }
+        };
 
-        if self.def.req.is_some() {
-            modifications.push(quote! { request = request.json(body); });
+        if self.def.req.is_some() && !self.def.runtime_body_formats.is_empty() {
+            let apply = match self.flavor {
+                ClientFlavor::Async => quote! { apply },
+                ClientFlavor::Blocking => quote! { apply_blocking },
+            };
+            modifications.push(quote! { request = body_format.#apply(request, #body); });
+        } else if let (Some(_), Some(ref sign_body)) = (&self.def.req, &self.def.sign_body) {
+            modifications.push(self.expand_signed_body(sign_body, &body));
+        } else if self.def.req.is_some() {
+            match self.def.body_format {
+                BodyFormat::Json => modifications.push(quote! { request = request.json(#body); }),
+                BodyFormat::Stream => modifications.push(quote! { request = request.body(body); }),
+                BodyFormat::Form => modifications.push(quote! { request = request.form(#body); }),
+                BodyFormat::RawJson => modifications.push(quote! {
+                    request = request
+                        .header(reqwest::header::CONTENT_TYPE, "application/json")
+                        .body(body);
+                }),
+                BodyFormat::Multipart => modifications.push(self.expand_multipart(&body)),
+            }
         }
         if self.def.query_params.is_some() {
             modifications.push(quote! { request = request.query(query_params); });
         }
-        if self.def.headers.is_some() {
-            modifications.push(quote! { request = request.headers(headers); });
+        if self.def.query_body.is_some() {
+            modifications.push(quote! { request = request.query(query_body); });
+        }
+        if !self.def.query_types.is_empty() {
+            let query_pairs = query_pairs_ident();
+            modifications.push(quote! { request = request.query(&#query_pairs); });
+        }
+        if self.ctx.extra_query {
+            modifications.push(quote! { request = request.query(extra); });
+        }
+        if let (true, Some(ref paginate)) = (self.ctx.page, &self.def.paginate) {
+            let cursor_param = &paginate.cursor_param;
+            modifications.push(quote! {
+                if let Some(cursor) = cursor {
+                    request = request.query(&[(#cursor_param, cursor)]);
+                }
+            });
+        }
+        // reqwest has no typed header support, so typed headers are encoded
+        // into a `HeaderMap` first.
+        let add_headers = if self.def.typed_headers {
+            quote! {
+                let mut typed_headers = reqwest::header::HeaderMap::new();
+                headers::HeaderMapExt::typed_insert(&mut typed_headers, headers);
+                request = request.headers(typed_headers);
+            }
+        } else {
+            quote! { request = request.headers(headers); }
+        };
+        match self.def.headers {
+            Some(ref headers) if option_inner(headers).is_some() => {
+                modifications.push(quote! {
+                    if let Some(headers) = headers {
+                        #add_headers
+                    }
+                });
+            }
+            Some(_) => modifications.push(add_headers),
+            None => {}
+        }
+        if self.def.extra_headers {
+            modifications.push(quote! {
+                for (name, value) in extra_headers {
+                    request = request.header(name.clone(), value.clone());
+                }
+            });
+        }
+        // Header names are emitted exactly as written; reqwest normalizes them on the wire.
+        for (name, value) in &self.def.static_headers {
+            modifications.push(quote! { request = request.header(#name, #value); });
         }
+        if self.def.if_match {
+            modifications.push(quote! {
+                request = request.header(reqwest::header::IF_MATCH, etag);
+            });
+        }
+        if let Some(ref accept_encoding) = self.def.accept_encoding {
+            modifications.push(quote! {
+                request = request.header(reqwest::header::ACCEPT_ENCODING, #accept_encoding);
+            });
+        }
+        if self.request_id {
+            modifications.push(quote! {
+                request = request.header("x-request-id", uuid::Uuid::new_v4().to_string());
+            });
+        }
+        if let Some(correlation_id) = self.correlation_id {
+            modifications.push(quote! {
+                if let Ok(correlation_id) = #correlation_id.try_with(|id| id.to_string()) {
+                    request = request.header("x-correlation-id", correlation_id);
+                }
+            });
+        }
+        // Overrides set through the `{Name}Ext` trait come last.
+        modifications.push(quote! {
+            for (name, value) in &self.header_overrides {
+                request = request.header(name.as_str(), value.as_str());
+            }
+            if !self.query_overrides.is_empty() {
+                request = request.query(&self.query_overrides);
+            }
+        });
 
         modifications
     }
+
+    /// Sends the body as a JSON `metadata` part followed by the `file` part.
+    fn expand_multipart(&self, body: &TokenStream) -> TokenStream {
+        let error_name = self.error_name;
+        quote! {
+            let metadata = serde_json::to_string(#body)
+                .map_err(|e| #error_name::Serialization(e.to_string()))?;
+            let metadata = reqwest::multipart::Part::text(metadata)
+                .mime_str("application/json")
+                .map_err(#error_name::from)?;
+            request = request.multipart(
+                reqwest::multipart::Form::new()
+                    .part("metadata", metadata)
+                    .part("file", file),
+            );
+        }
+    }
 }
 
 pub struct ResponseExpander<'a> {
-    res: Option<&'a syn::Type>,
+    def: &'a EndpointDef,
+    input: &'a HttpProviderInput,
     error_name: &'a Ident,
+    flavor: ClientFlavor,
+    page: bool,
+    writer: bool,
 }
 
 impl<'a> ResponseExpander<'a> {
-    pub fn new(res: Option<&'a syn::Type>, error_name: &'a Ident) -> Self {
-        Self { res, error_name }
+    pub fn new(
+        def: &'a EndpointDef,
+        input: &'a HttpProviderInput,
+        error_name: &'a Ident,
+        flavor: ClientFlavor,
+    ) -> Self {
+        Self {
+            def,
+            input,
+            error_name,
+            flavor,
+            page: false,
+            writer: false,
+        }
+    }
+
+    /// Returns a single page paired with the next cursor when `page` is set.
+    pub fn paged(mut self, page: bool) -> Self {
+        self.page = page;
+        self
+    }
+
+    /// Streams the body into `writer` and returns the byte count when
+    /// `writer` is set.
+    pub fn writing(mut self, writer: bool) -> Self {
+        self.writer = writer;
+        self
     }
 
     pub fn expand(&self) -> TokenStream {
         let error_name = self.error_name;
+        let awaited = self.flavor.awaited();
+        let (sent_at, elapsed) = if self.def.res_format == ResFormat::Traced {
+            (
+                quote! { let sent_at = std::time::Instant::now(); },
+                quote! { let elapsed = sent_at.elapsed(); },
+            )
+        } else {
+            (quote! {}, quote! {})
+        };
 
+        let query_defaults = expand_query_defaults(self.def);
+        let execute = self.expand_execute();
+        let capture_location = if self.def.location && !self.writer {
+            quote! {
+                location = response
+                    .headers()
+                    .get(reqwest::header::LOCATION)
+                    .and_then(|value| value.to_str().ok())
+                    .map(str::to_string);
+            }
+        } else {
+            quote! {}
+        };
         let response = quote! {
-            let response = request
-                .send()
-                .await
-                .map_err(#error_name::from)?;
+            let mut request = request.build().map_err(#error_name::from)?;
+            #query_defaults
+            if let Some(ref interceptor) = self.interceptor {
+                request = interceptor(request);
+            }
+            #sent_at
+            #execute
+            #elapsed
+            if let Some(ref on_response) = self.on_response {
+                on_response(&response);
+            }
+            #capture_location
         };
 
+        let is_success = match self.def.status_ok_with {
+            Some(ref status_ok_with) => quote! { #status_ok_with(status) },
+            None => quote! { status.is_success() },
+        };
+        let default_on_status = self.expand_default_on_status();
+        let not_found = self.expand_not_found();
+        let error_body = self.expand_error_body();
+        let precondition_failed = if self.def.if_match {
+            quote! {
+                if status == reqwest::StatusCode::PRECONDITION_FAILED {
+                    return Err(#error_name::PreconditionFailed);
+                }
+            }
+        } else {
+            quote! {}
+        };
+        let status_check = if self.def.check_status {
+            quote! {
+                if !#is_success {
+                    let reason = status.canonical_reason().unwrap_or("Unknown").to_string();
+                    #error_body
+                    return Err(#error_name::Http {
+                        status: status.as_u16(),
+                        reason,
+                    });
+                }
+            }
+        } else {
+            quote! {}
+        };
         let handle_error = quote! {
             let status = response.status();
-            if !status.is_success() {
-                let reason = status.canonical_reason().unwrap_or("Unknown").to_string();
-                return Err(#error_name::Http {
+            #not_found
+            #default_on_status
+            #precondition_failed
+            #status_check
+        };
+
+        let deserialized_response = match (self.def.res_format, &self.def.res) {
+            _ if self.writer => self.expand_write(),
+            (_, Some(res)) if self.page => match self.def.paginate {
+                Some(ref paginate) => self.expand_page(paginate, res),
+                None => unreachable!("pages are only expanded for paginated endpoints"),
+            },
+            (ResFormat::Status, _) => quote! {
+                Ok(status)
+            },
+            (ResFormat::Json, res) if self.def.graphql => self.expand_graphql(res.as_ref()),
+            (ResFormat::Json, Some(res)) if self.def.batch => self.expand_batch_results(res),
+            (ResFormat::Json, Some(res)) => self.expand_deserialization(res),
+            (ResFormat::Auto, Some(res)) => {
+                let content_type_check = self.expand_content_type_check();
+                let deserialization = self.expand_deserialization(res);
+                quote! {
+                    #content_type_check
+                    #deserialization
+                }
+            }
+            (ResFormat::Json | ResFormat::Auto, None) => quote! {
+                Ok(())
+            },
+            (ResFormat::Traced, res) => self.expand_traced(res.as_ref()),
+            (ResFormat::JsonOrText, res) => self.expand_json_or_text(res.as_ref()),
+            (ResFormat::WithStatus, Some(res)) => {
+                let deserialization = self.expand_deserialization(res);
+                quote! { { #deserialization }.map(|body| (body, status)) }
+            }
+            (ResFormat::WithStatus, None) => quote! {
+                Ok(((), status))
+            },
+            (ResFormat::WithBytes, Some(res)) => {
+                let parse = self.expand_bytes_parse(res);
+                quote! {
+                    let bytes = response.bytes()#awaited.map_err(#error_name::from)?;
+                    let body = { #parse }?;
+                    Ok((body, bytes))
+                }
+            }
+            (ResFormat::WithBytes, None) => quote! {
+                Ok(((), response.bytes()#awaited.map_err(#error_name::from)?))
+            },
+            (ResFormat::Sse, _) => self.expand_sse(),
+            (ResFormat::ToWriter, _) => quote! {
+                Ok(response.bytes()#awaited.map_err(#error_name::from)?.to_vec())
+            },
+            (ResFormat::Headers, _) => quote! {
+                Ok(response.headers().clone())
+            },
+        };
+
+        let handler = quote! {
+            #response
+            #handle_error
+            #deserialized_response
+        };
+        self.expand_measure(self.expand_location(self.expand_res_map(handler)))
+    }
+
+    /// Sends the request, sending it again up to `retries` times while it fails
+    /// to connect, times out or gets a 5xx status. Non-idempotent methods are
+    /// sent once unless `idempotent_only` is disabled, and so are requests
+    /// whose streamed body cannot be cloned.
+    ///
+    /// A retry waits `retry_backoff_ms`, doubled on every further retry, and
+    /// then for a permit of the `rate_limit` like the first attempt did.
+    fn expand_execute(&self) -> TokenStream {
+        let error_name = self.error_name;
+        let awaited = self.flavor.awaited();
+        let options = &self.input.options;
+        let retries = options.retries;
+        if retries == 0 || !(self.def.method.is_idempotent() || options.retry_non_idempotent) {
+            return quote! {
+                let response = self
+                    .client
+                    .execute(request)
+                    #awaited
+                    .map_err(#error_name::from)?;
+            };
+        }
+
+        let (backoff_init, backoff) = match options.retry_backoff_ms {
+            Some(ms) => {
+                let sleep = match self.flavor {
+                    ClientFlavor::Async => quote! { tokio::time::sleep(backoff).await; },
+                    ClientFlavor::Blocking => quote! { std::thread::sleep(backoff); },
+                };
+                (
+                    quote! { let mut backoff = std::time::Duration::from_millis(#ms); },
+                    quote! {
+                        #sleep
+                        backoff = backoff.saturating_mul(2);
+                    },
+                )
+            }
+            None => (quote! {}, quote! {}),
+        };
+        let rate_limit = if options.rate_limit.is_some() {
+            quote! { self.rate_limiter.until_ready().await; }
+        } else {
+            quote! {}
+        };
+
+        quote! {
+            let mut retries_left: u32 = #retries;
+            #backoff_init
+            let response = loop {
+                let retry = if retries_left > 0 {
+                    request.try_clone()
+                } else {
+                    None
+                };
+                let result = self.client.execute(request)#awaited;
+                request = match (result, retry) {
+                    (Ok(response), Some(retry)) if response.status().is_server_error() => retry,
+                    (Err(err), Some(retry)) if err.is_connect() || err.is_timeout() => retry,
+                    (result, _) => break result.map_err(#error_name::from)?,
+                };
+                retries_left -= 1;
+                #backoff
+                #rate_limit
+            };
+        }
+    }
+
+    /// Passes the result of the handler through the endpoint's `res_map`
+    /// function, which for pages only maps the page.
+    fn expand_res_map(&self, handler: TokenStream) -> TokenStream {
+        let Some(ref res_map) = self.def.res_map else {
+            return handler;
+        };
+        if self.writer {
+            return handler;
+        }
+
+        let with = &res_map.with;
+        let format = format_type(self.def, &self.input.struct_name);
+        let (res, map) = if self.page {
+            (
+                quote! { (#format, Option<String>) },
+                quote! { |(page, cursor)| (#with(page), cursor) },
+            )
+        } else {
+            (format, quote! { #with })
+        };
+        let result = self.flavor.wrap_result(&handler, &res, self.error_name);
+        quote! {
+            #result
+            result.map(#map)
+        }
+    }
+
+    /// Pairs the response with its `Location` header, which the handler
+    /// captures before the body is read.
+    fn expand_location(&self, handler: TokenStream) -> TokenStream {
+        if !self.def.location || self.writer {
+            return handler;
+        }
+
+        let res = mapped_type(self.def, &self.input.struct_name);
+        let result = self.flavor.wrap_result(&handler, &res, self.error_name);
+        quote! {
+            let mut location: Option<String> = None;
+            #result
+            result.map(|body| (body, location))
+        }
+    }
+
+    /// Pairs the response with the time from sending the request until the
+    /// response was handled, body included.
+    fn expand_measure(&self, handler: TokenStream) -> TokenStream {
+        if !self.def.measure {
+            return handler;
+        }
+
+        let res = located_type(self.def, &self.input.struct_name);
+        let result = self.flavor.wrap_result(&handler, &res, self.error_name);
+        quote! {
+            let started_at = std::time::Instant::now();
+            #result
+            result.map(|body| (body, started_at.elapsed()))
+        }
+    }
+
+    /// Streams the events of the body, reading the next chunk only once the
+    /// events parsed so far were consumed.
+    fn expand_sse(&self) -> TokenStream {
+        let error_name = self.error_name;
+        let sse_parser = sse_parser_name(&self.input.struct_name);
+        let sse_stream = sse_stream_name(&self.input.struct_name);
+        quote! {
+            let state = (response, #sse_parser::default(), std::collections::VecDeque::new());
+            let events = futures::stream::try_unfold(
+                state,
+                |(mut response, mut parser, mut pending)| async move {
+                    loop {
+                        if let Some(event) = pending.pop_front() {
+                            return Ok(Some((event, (response, parser, pending))));
+                        }
+                        match response.chunk().await.map_err(#error_name::from)? {
+                            Some(chunk) => pending.extend(parser.feed(&chunk)),
+                            None => return Ok(None),
+                        }
+                    }
+                },
+            );
+            Ok(Box::pin(events) as #sse_stream)
+        }
+    }
+
+    /// Copies the body into `writer`, counting the bytes written.
+    fn expand_write(&self) -> TokenStream {
+        let error_name = self.error_name;
+        match self.flavor {
+            ClientFlavor::Async => quote! {
+                let mut response = response;
+                let mut written = 0u64;
+                while let Some(chunk) = response.chunk().await.map_err(#error_name::from)? {
+                    std::io::Write::write_all(writer, &chunk).map_err(#error_name::Io)?;
+                    written += chunk.len() as u64;
+                }
+                Ok(written)
+            },
+            ClientFlavor::Blocking => quote! {
+                let mut response = response;
+                std::io::copy(&mut response, writer).map_err(#error_name::Io)
+            },
+        }
+    }
+
+    /// Pairs the deserialized body with the final URL, the status and the time
+    /// until the response headers arrived.
+    fn expand_traced(&self, res: Option<&syn::Type>) -> TokenStream {
+        let traced_response = traced_response_name(&self.input.struct_name);
+        let body = match res {
+            Some(res) => {
+                let deserialization = self.expand_deserialization(res);
+                quote! { let body: #res = { #deserialization }?; }
+            }
+            None => quote! { let body = (); },
+        };
+        quote! {
+            let url = response.url().to_string();
+            #body
+            Ok(#traced_response {
+                body,
+                url,
+                status: status.as_u16(),
+                elapsed,
+            })
+        }
+    }
+
+    /// Unwraps a GraphQL `{ data, errors }` envelope. GraphQL servers report
+    /// failures with a 200 status, so a non-empty `errors` array is an error.
+    fn expand_graphql(&self, res: Option<&syn::Type>) -> TokenStream {
+        let error_name = self.error_name;
+        let graphql_error = graphql_error_name(&self.input.struct_name);
+        let data_ty: syn::Type = match res {
+            Some(res) => res.clone(),
+            None => syn::parse_quote! { serde::de::IgnoredAny },
+        };
+        let envelope: syn::Type = syn::parse_quote! { GraphQlEnvelope };
+        let deserialization = self.expand_deserialization(&envelope);
+        let data = match res {
+            Some(_) => quote! {
+                envelope.data.ok_or_else(|| {
+                    #error_name::Deserialization("GraphQL response has no `data`".to_string())
+                })
+            },
+            None => quote! { Ok(()) },
+        };
+        quote! {
+            #[derive(serde::Deserialize)]
+            struct GraphQlEnvelope {
+                data: Option<#data_ty>,
+                #[serde(default)]
+                errors: Vec<#graphql_error>,
+            }
+
+            let envelope: GraphQlEnvelope = { #deserialization }?;
+            if !envelope.errors.is_empty() {
+                return Err(#error_name::GraphQlErrors(envelope.errors));
+            }
+            #data
+        }
+    }
+
+    /// Deserializes the results of a batch, which must hold exactly one result
+    /// per operation sent.
+    fn expand_batch_results(&self, res: &syn::Type) -> TokenStream {
+        let error_name = self.error_name;
+        let deserialization = self.expand_deserialization(res);
+        quote! {
+            let results: #res = { #deserialization }?;
+            if results.len() != body.len() {
+                return Err(#error_name::Deserialization(format!(
+                    "batch returned {} results for {} operations",
+                    results.len(),
+                    body.len()
+                )));
+            }
+            Ok(results)
+        }
+    }
+
+    /// Branches on the `Content-Type`: JSON bodies are deserialized into `res`
+    /// and anything else is returned as text.
+    fn expand_json_or_text(&self, res: Option<&syn::Type>) -> TokenStream {
+        let error_name = self.error_name;
+        let awaited = self.flavor.awaited();
+        let json_or_text = json_or_text_name(&self.input.struct_name);
+        let content_type = self.expand_content_type();
+        let json = match res {
+            Some(res) => {
+                let deserialization = self.expand_deserialization(res);
+                quote! { { #deserialization }.map(#json_or_text::Json) }
+            }
+            None => quote! { Ok(#json_or_text::Json(())) },
+        };
+        quote! {
+            #content_type
+            if is_json {
+                #json
+            } else {
+                response
+                    .text()
+                    #awaited
+                    .map(#json_or_text::Text)
+                    .map_err(#error_name::from)
+            }
+        }
+    }
+
+    /// An `Option<T>` response type models a resource that may not exist, so a
+    /// 404 yields `Ok(None)` while a JSON `null` body still deserializes to `None`.
+    fn expand_not_found(&self) -> TokenStream {
+        if matches!(
+            self.def.res_format,
+            ResFormat::Traced
+                | ResFormat::JsonOrText
+                | ResFormat::WithStatus
+                | ResFormat::WithBytes
+                | ResFormat::Sse
+        ) {
+            return quote! {};
+        }
+        match self.def.res {
+            Some(ref res) if option_inner(res).is_some() => quote! {
+                if status == reqwest::StatusCode::NOT_FOUND {
+                    return Ok(None);
+                }
+            },
+            _ => quote! {},
+        }
+    }
+
+    /// Deserializes a failed response into the `err` type. A body that does not
+    /// parse, or a status outside the `on` list, falls back to the `Http` variant.
+    fn expand_error_body(&self) -> TokenStream {
+        let Some(ref error_body) = self.def.err else {
+            return quote! {};
+        };
+
+        let error_name = self.error_name;
+        let awaited = self.flavor.awaited();
+        let ty = &error_body.ty;
+        let variant = &error_body.variant;
+        let parse = quote! {
+            if let Ok(body) = response.json::<#ty>()#awaited {
+                return Err(#error_name::#variant {
                     status: status.as_u16(),
-                    reason,
+                    body,
                 });
             }
         };
+        if error_body.statuses.is_empty() {
+            return parse;
+        }
+
+        let statuses = &error_body.statuses;
+        quote! {
+            if [#(#statuses),*].contains(&status.as_u16()) {
+                #parse
+            }
+        }
+    }
+
+    fn expand_default_on_status(&self) -> TokenStream {
+        if self.def.default_on_status.is_empty() {
+            return quote! {};
+        }
+
+        let statuses = &self.def.default_on_status;
+        quote! {
+            if [#(#statuses),*].contains(&status.as_u16()) {
+                return Ok(Default::default());
+            }
+        }
+    }
+
+    /// Rejects responses whose `Content-Type` is not `application/json` or a
+    /// `+json` suffix type, e.g. HTML error pages served with a 200 status.
+    /// Binds `content_type` and whether it is `is_json`, i.e. `application/json`
+    /// or a `+json` suffix type, ignoring parameters such as `charset`.
+    fn expand_content_type(&self) -> TokenStream {
+        quote! {
+            let content_type = response
+                .headers()
+                .get(reqwest::header::CONTENT_TYPE)
+                .and_then(|value| value.to_str().ok())
+                .unwrap_or("")
+                .to_string();
+            let essence = content_type
+                .split(';')
+                .next()
+                .unwrap_or("")
+                .trim()
+                .to_ascii_lowercase();
+            let is_json = essence == "application/json" || essence.ends_with("+json");
+        }
+    }
+
+    fn expand_content_type_check(&self) -> TokenStream {
+        let error_name = self.error_name;
+        let content_type = self.expand_content_type();
+        quote! {
+            #content_type
+            if !is_json {
+                return Err(#error_name::Deserialization(format!(
+                    "expected a JSON response but got content type `{}`",
+                    content_type
+                )));
+            }
+        }
+    }
 
-        let deserialized_response = match self.res {
-            Some(res) => quote! {
+    fn expand_deserialization(&self, res: &syn::Type) -> TokenStream {
+        let error_name = self.error_name;
+        let awaited = self.flavor.awaited();
+        if self.def.json_recursion_limit.is_none()
+            && self.input.options.json_backend == JsonBackend::Serde
+            && !self.input.options.log_bodies
+            && !self.input.options.strict_json
+        {
+            return quote! {
                 response
                     .json::<#res>()
-                    .await
+                    #awaited
                     .map_err(|e| #error_name::Deserialization(e.to_string()))
+            };
+        }
+
+        let parse = self.expand_bytes_parse(res);
+        quote! {
+            let bytes = response.bytes()#awaited.map_err(#error_name::from)?;
+            #parse
+        }
+    }
+
+    /// Parses the already read `bytes` into `res`, honoring the endpoint's
+    /// recursion limit and the provider's JSON backend.
+    fn expand_bytes_parse(&self, res: &syn::Type) -> TokenStream {
+        let error_name = self.error_name;
+        let depth_check = self.expand_depth_check();
+        let response_logging = if self.input.options.log_bodies {
+            quote! {
+                if tracing::enabled!(tracing::Level::TRACE) {
+                    let response_body = String::from_utf8_lossy(&bytes);
+                    tracing::trace!(%response_body, "received response body");
+                }
+            }
+        } else {
+            quote! {}
+        };
+        let parse = match (
+            self.input.options.json_backend,
+            self.input.options.strict_json,
+        ) {
+            (JsonBackend::Serde, false) => quote! {
+                serde_json::from_slice::<#res>(&bytes)
             },
-            None => quote! {
-                Ok(())
+            // The error's `Display` prefixes the inner message with the field path.
+            (JsonBackend::PathToError, false) => quote! {
+                serde_path_to_error::deserialize::<_, #res>(
+                    &mut serde_json::Deserializer::from_slice(&bytes),
+                )
             },
+            (backend, true) => {
+                let deserializer = quote! {
+                    serde_ignored::Deserializer::new(
+                        &mut serde_json::Deserializer::from_slice(&bytes),
+                        &mut record_unknown,
+                    )
+                };
+                let deserialize = match backend {
+                    JsonBackend::Serde => quote! {
+                        <#res as serde::Deserialize>::deserialize(#deserializer)
+                    },
+                    JsonBackend::PathToError => quote! {
+                        serde_path_to_error::deserialize::<_, #res>(#deserializer)
+                    },
+                };
+                // Fields the type skips are reported by their path, e.g. `user.nickname`.
+                quote! {
+                    {
+                        let mut unknown_fields: Vec<String> = Vec::new();
+                        let mut record_unknown =
+                            |path: serde_ignored::Path<'_>| unknown_fields.push(path.to_string());
+                        let result = #deserialize.map_err(|e| e.to_string());
+                        result.and_then(|body| {
+                            if unknown_fields.is_empty() {
+                                Ok(body)
+                            } else {
+                                Err(format!("unknown fields: {}", unknown_fields.join(", ")))
+                            }
+                        })
+                    }
+                }
+            }
         };
 
         quote! {
-            #response
-            #handle_error
-            #deserialized_response
+            #response_logging
+            #depth_check
+            #parse.map_err(|e| #error_name::Deserialization(e.to_string()))
+        }
+    }
+
+    /// Pairs a page deserialized into `res` with the cursor of the next page,
+    /// which is `None` on the last page.
+    fn expand_page(&self, paginate: &Paginate, res: &syn::Type) -> TokenStream {
+        let error_name = self.error_name;
+        let awaited = self.flavor.awaited();
+        let parse = self.expand_bytes_parse(res);
+        let (header_cursor, next_cursor) = match paginate.cursor {
+            CursorSource::Header(ref header) => (
+                quote! {
+                    let next_cursor = response
+                        .headers()
+                        .get(#header)
+                        .and_then(|value| value.to_str().ok())
+                        .map(str::to_string);
+                },
+                quote! {},
+            ),
+            CursorSource::Field(ref field) => (
+                quote! {},
+                quote! {
+                    let next_cursor = serde_json::from_slice::<serde_json::Value>(&bytes)
+                        .ok()
+                        .and_then(|body| match body.get(#field)? {
+                            serde_json::Value::String(cursor) => Some(cursor.clone()),
+                            serde_json::Value::Number(cursor) => Some(cursor.to_string()),
+                            _ => None,
+                        });
+                },
+            ),
+        };
+        quote! {
+            #header_cursor
+            let bytes = response.bytes()#awaited.map_err(#error_name::from)?;
+            #next_cursor
+            let page: #res = { #parse }?;
+            Ok((page, next_cursor.filter(|cursor| !cursor.is_empty())))
+        }
+    }
+
+    fn expand_depth_check(&self) -> TokenStream {
+        let Some(limit) = self.def.json_recursion_limit else {
+            return quote! {};
+        };
+
+        let error_name = self.error_name;
+        // serde_json only exposes a fixed recursion limit, so the nesting depth
+        // is checked with a single pass over the raw bytes before parsing.
+        quote! {
+            let mut depth = 0usize;
+            let mut in_string = false;
+            let mut escaped = false;
+            for &byte in bytes.iter() {
+                if in_string {
+                    match byte {
+                        _ if escaped => escaped = false,
+                        b'\\' => escaped = true,
+                        b'"' => in_string = false,
+                        _ => {}
+                    }
+                    continue;
+                }
+                match byte {
+                    b'"' => in_string = true,
+                    b'{' | b'[' => {
+                        depth += 1;
+                        if depth > #limit {
+                            return Err(#error_name::Deserialization(format!(
+                                "JSON nesting exceeds the recursion limit of {}",
+                                #limit
+                            )));
+                        }
+                    }
+                    b'}' | b']' => depth = depth.saturating_sub(1),
+                    _ => {}
+                }
+            }
         }
     }
 }
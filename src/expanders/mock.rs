@@ -0,0 +1,141 @@
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::Ident;
+
+use crate::input::HttpProviderInput;
+
+use super::method::{
+    call_error_type, response_type, AttrsExpander, ClientFlavor, FnNameExpander, SignatureExpander,
+};
+
+/// Expands the `{Name}Mock` of `test_helpers`, a stand-in implementing the
+/// provider traits with canned responses, only compiled under `#[cfg(test)]`.
+///
+/// Every endpoint gets a slot holding the next result of its method and an
+/// `expect_*` setter filling it. A call takes the result out of the slot, and
+/// a call with an empty slot panics naming the setter.
+pub struct MockExpander<'a> {
+    input: &'a HttpProviderInput,
+    error_name: &'a Ident,
+    /// The traits to implement with the flavor of their signatures.
+    traits: Vec<(Ident, ClientFlavor)>,
+}
+
+impl<'a> MockExpander<'a> {
+    pub fn new(
+        input: &'a HttpProviderInput,
+        error_name: &'a Ident,
+        traits: Vec<(Ident, ClientFlavor)>,
+    ) -> Self {
+        Self {
+            input,
+            error_name,
+            traits,
+        }
+    }
+
+    pub fn expand(&self) -> TokenStream {
+        let struct_name = &self.input.struct_name;
+        let mock_name = Ident::new(&format!("{}Mock", struct_name), struct_name.span());
+        let vis = self.input.options.struct_vis();
+        let doc = format!(
+            " A stand-in for `{}` answering every call with the result set by its\n \
+             `expect_*` setter. Each result answers a single call.",
+            struct_name
+        );
+
+        let mut fields = Vec::new();
+        let mut setters = Vec::new();
+        for def in &self.input.endpoints {
+            let fn_name = FnNameExpander::new(def, &self.input.options).expand();
+            let setter = Ident::new(&format!("expect_{}", fn_name), fn_name.span());
+            let res = response_type(def, struct_name);
+            let error_name = call_error_type(def, self.error_name);
+            let cfg = AttrsExpander::new(def).expand_cfg();
+            fields.push(quote! {
+                #cfg
+                #fn_name: std::sync::Mutex<Option<Result<#res, #error_name>>>,
+            });
+            setters.push(quote! {
+                /// Sets the result of the next call to the endpoint.
+                #cfg
+                pub fn #setter(&self, result: Result<#res, #error_name>) -> &Self {
+                    *self
+                        .#fn_name
+                        .lock()
+                        .unwrap_or_else(std::sync::PoisonError::into_inner) = Some(result);
+                    self
+                }
+            });
+        }
+
+        let trait_impls = self
+            .traits
+            .iter()
+            .map(|(trait_name, flavor)| self.expand_trait_impl(&mock_name, trait_name, *flavor));
+
+        quote! {
+            #[doc = #doc]
+            #[cfg(test)]
+            #[derive(Default)]
+            #vis struct #mock_name {
+                #(#fields)*
+            }
+
+            #[cfg(test)]
+            impl #mock_name {
+                #(#setters)*
+            }
+
+            #(#trait_impls)*
+        }
+    }
+
+    fn expand_trait_impl(
+        &self,
+        mock_name: &Ident,
+        trait_name: &Ident,
+        flavor: ClientFlavor,
+    ) -> TokenStream {
+        let boxed = flavor == ClientFlavor::Async && self.input.options.boxed_futures;
+        let methods = self.input.endpoints.iter().map(|def| {
+            // The mock implements the trait for the generated error, which is
+            // the default of a `generic_error` trait's parameter.
+            let signature =
+                SignatureExpander::new(def, self.input, self.error_name, flavor).expand();
+            let fn_name = FnNameExpander::new(def, &self.input.options).expand();
+            let cfg = AttrsExpander::new(def).expand_cfg();
+            let unexpected = format!(
+                "unexpected call to `{0}`, set its result with `expect_{0}`",
+                fn_name
+            );
+            let result = quote! {
+                self.#fn_name
+                    .lock()
+                    .unwrap_or_else(std::sync::PoisonError::into_inner)
+                    .take()
+                    .expect(#unexpected)
+            };
+            let body = if boxed {
+                quote! { Box::pin(std::future::ready(#result)) }
+            } else {
+                result
+            };
+
+            quote! {
+                #cfg
+                #signature {
+                    #body
+                }
+            }
+        });
+
+        quote! {
+            #[cfg(test)]
+            #[allow(deprecated, unused_variables)]
+            impl #trait_name for #mock_name {
+                #(#methods)*
+            }
+        }
+    }
+}
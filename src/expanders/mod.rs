@@ -1,18 +1,69 @@
 use crate::{
     error::{MacroError, MacroResult},
-    input::HttpProviderInput,
+    input::{BodyFormat, EndpointDef, HttpProviderInput, MapFn, RatePeriod, ResFormat},
 };
+use heck::ToShoutySnakeCase;
 use proc_macro2::TokenStream;
-use quote::quote;
-use syn::Ident;
+use quote::{quote, quote_spanned};
+use syn::{spanned::Spanned, Ident, LitStr};
 
 pub mod error;
 pub mod interface;
 pub mod method;
+pub mod mock;
 
 pub use error::ErrorExpander;
 pub use interface::TraitExpander;
-pub use method::MethodExpander;
+pub use method::{
+    body_format_name, generated_method_names, graphql_error_name, has_static_url,
+    json_or_text_name, option_inner, path_param_names, sse_event_name, sse_parser_name,
+    sse_stream_name, traced_response_name, vec_inner, AttrsExpander, ClientFlavor, FnNameExpander,
+    MethodExpander,
+};
+pub use mock::MockExpander;
+
+/// Names of the fields every provider struct has, which fields named by the
+/// invocation such as a `sign_body` secret must not reuse.
+const RESERVED_FIELDS: &[&str] = &[
+    "url",
+    "client",
+    "timeout",
+    "on_response",
+    "interceptor",
+    "header_overrides",
+    "query_overrides",
+    "rate_limiter",
+    "static_urls",
+    "state",
+];
+
+/// Names of the methods every provider has, which no endpoint method may
+/// reuse.
+const PROVIDER_METHODS: &[&str] = &[
+    "new",
+    "new_with",
+    "from_env",
+    "from_client",
+    "endpoints",
+    "with_timeout",
+    "with_request_interceptor",
+    "with_response_hook",
+    "with_header",
+    "with_query",
+    "into_dyn",
+    "routes",
+];
+
+/// Tokens threading the optional state type parameter through the provider.
+#[derive(Default)]
+struct StateTokens {
+    decl_generics: TokenStream,
+    impl_generics: TokenStream,
+    ty_generics: TokenStream,
+    field: TokenStream,
+    init: TokenStream,
+    methods: TokenStream,
+}
 
 pub struct HttpProviderExpander {
     input: HttpProviderInput,
@@ -29,68 +80,1334 @@ impl HttpProviderExpander {
         let struct_name = &self.input.struct_name;
         let error_name = Ident::new(&format!("{}Error", struct_name), struct_name.span());
 
-        let error_type = ErrorExpander::new(&error_name).expand();
-        let trait_def = self.expand_trait_def(&error_name)?;
-        let methods = self.expand_methods(&error_name)?;
-        let struct_impl = self.expand_struct_impl(&methods);
+        let error_bodies = self
+            .input
+            .endpoints
+            .iter()
+            .filter_map(|def| def.err.as_ref());
+        let graphql_error = graphql_error_name(struct_name);
+        let uses_graphql = self.input.endpoints.iter().any(|def| def.graphql);
+        let error_type = ErrorExpander::new(
+            &error_name,
+            error_bodies,
+            uses_graphql.then_some(&graphql_error),
+        )
+        .expand();
+        let graphql_error = if uses_graphql {
+            self.expand_graphql_error(&graphql_error)
+        } else {
+            quote! {}
+        };
+        let body_format = self.expand_body_format();
+        let endpoint_info = self.expand_endpoint_info();
+        let traced_response = self.expand_traced_response();
+        let json_or_text = self.expand_json_or_text();
+        let sse = self.expand_sse(&error_name);
+        let warnings = self.expand_warnings();
+        let provider = self.expand_provider(&error_name, ClientFlavor::Async)?;
+        let blocking_provider = if self.input.options.both {
+            self.expand_provider(&error_name, ClientFlavor::Blocking)?
+        } else {
+            quote! {}
+        };
+        let ext = self.expand_ext();
+        let mock = self.expand_mock(&error_name);
 
         Ok(quote! {
             #error_type
+            #graphql_error
+            #body_format
+            #endpoint_info
+            #traced_response
+            #json_or_text
+            #sse
+            #warnings
+            #provider
+            #ext
+            #blocking_provider
+            #mock
+        })
+    }
+
+    /// Expands the `{Name}Ext` trait of per-call overrides, implemented by every
+    /// generated provider.
+    fn expand_ext(&self) -> TokenStream {
+        let ext_name = self.ext_name();
+        let vis = self.input.options.trait_vis();
+        let doc = format!(
+            " Per-call overrides of `{0}`. Each method returns a clone of the\n \
+             provider that sends the override with every request, on top of the\n \
+             endpoint's own headers and query, e.g.\n \
+             `client.with_header(\"x-tenant\", \"acme\").get_users()`.",
+            self.input.struct_name
+        );
+        quote! {
+            #[doc = #doc]
+            #vis trait #ext_name: Sized {
+                /// Adds a header to every request. An invalid name or value fails
+                /// the call with a `Request` error.
+                fn with_header(&self, name: impl Into<String>, value: impl Into<String>) -> Self;
+
+                /// Adds a query pair to every request.
+                fn with_query(&self, key: impl Into<String>, value: impl Into<String>) -> Self;
+            }
+        }
+    }
+
+    /// Expands the `{Name}Mock` of `test_helpers`, implementing the trait of
+    /// every generated provider.
+    fn expand_mock(&self, error_name: &Ident) -> TokenStream {
+        if !self.input.options.test_helpers {
+            return quote! {};
+        }
+
+        let mut traits = vec![(self.trait_name(ClientFlavor::Async), ClientFlavor::Async)];
+        if self.input.options.both {
+            traits.push((
+                self.trait_name(ClientFlavor::Blocking),
+                ClientFlavor::Blocking,
+            ));
+        }
+        MockExpander::new(&self.input, error_name, traits).expand()
+    }
+
+    /// Expands a warning for every `req` on a method that normally has no body.
+    /// Proc macros cannot emit warnings on stable, so each one is the use of a
+    /// deprecated item spanned at the `req` type, whose note is the message.
+    fn expand_warnings(&self) -> TokenStream {
+        let warnings = self
+            .input
+            .endpoints
+            .iter()
+            .filter(|def| def.method.is_bodyless())
+            .filter_map(|def| {
+                let req = def.req.as_ref()?;
+                let note = format!(
+                    "`req` on a {} endpoint is unusual and many servers ignore the body; \
+                     set `strict: true` to reject it",
+                    def.method.verb()
+                );
+                Some(quote_spanned! {req.span()=>
+                    const _: () = {
+                        #[deprecated(note = #note)]
+                        struct RequestBodyOnBodylessMethod;
+                        let _ = RequestBodyOnBodylessMethod;
+                    };
+                })
+            });
+        quote! { #(#warnings)* }
+    }
+
+    /// Expands the trait and struct of the provider built on `flavor`'s client.
+    fn expand_provider(
+        &self,
+        error_name: &Ident,
+        flavor: ClientFlavor,
+    ) -> MacroResult<TokenStream> {
+        let trait_def = self.expand_trait_def(error_name, flavor)?;
+        let methods = self.expand_methods(error_name, flavor)?;
+        let variants = self.expand_variants(error_name, flavor)?;
+        let struct_impl = self.expand_struct_impl(error_name, flavor, &methods, &variants);
+
+        Ok(quote! {
             #trait_def
             #struct_impl
         })
     }
 
-    fn expand_trait_def(&self, error_name: &Ident) -> MacroResult<TokenStream> {
-        let trait_name = self.trait_name();
-        TraitExpander::new(&self.input.endpoints, &trait_name, &error_name).expand()
+    /// Expands the struct describing an endpoint, returned by `endpoints()`.
+    fn expand_endpoint_info(&self) -> TokenStream {
+        let endpoint_info = self.endpoint_info_name();
+        quote! {
+            #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+            pub struct #endpoint_info {
+                /// Name of the generated method.
+                pub name: &'static str,
+                /// Uppercase HTTP method, e.g. `"GET"`.
+                pub method: &'static str,
+                /// Path template with `{param}` placeholders.
+                pub path: &'static str,
+                /// Whether the endpoint sends a request body.
+                pub has_body: bool,
+                /// Names of the path template placeholders, in order.
+                pub path_params: &'static [&'static str],
+                /// The method and path template, e.g. `"GET /users/{id}"`, as
+                /// also returned by `as_ref()` and `Display`.
+                pub route: &'static str,
+            }
+
+            impl std::fmt::Display for #endpoint_info {
+                fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                    f.write_str(self.route)
+                }
+            }
+
+            impl AsRef<str> for #endpoint_info {
+                fn as_ref(&self) -> &str {
+                    self.route
+                }
+            }
+        }
     }
 
-    fn expand_methods(&self, error_name: &Ident) -> MacroResult<Vec<TokenStream>> {
+    /// Expands the `endpoints()` metadata entries in declaration order.
+    fn expand_endpoint_infos(&self) -> Vec<TokenStream> {
+        let endpoint_info = self.endpoint_info_name();
         self.input
             .endpoints
             .iter()
-            .map(|def| MethodExpander::new(def, error_name).expand())
+            .map(|def| {
+                let name = FnNameExpander::new(def, &self.input.options)
+                    .expand()
+                    .to_string();
+                let method = def.method.verb();
+                let path = def
+                    .path
+                    .as_ref()
+                    .map_or_else(|| "/".to_string(), |p| p.value());
+                let has_body = def.req.is_some();
+                let path_params = path_param_names(&path);
+                let route = format!("{} {}", method, path);
+                let cfg = AttrsExpander::new(def).expand_cfg();
+                quote! {
+                    #cfg
+                    endpoints.push(#endpoint_info {
+                        name: #name,
+                        method: #method,
+                        path: #path,
+                        has_body: #has_body,
+                        path_params: &[#(#path_params),*],
+                        route: #route,
+                    });
+                }
+            })
             .collect()
     }
 
-    fn expand_struct_impl(&self, methods: &[TokenStream]) -> TokenStream {
-        let struct_name = &self.input.struct_name;
-        let trait_name = self.trait_name();
+    /// Expands the wrapper returned by `res_format: traced` endpoints, only
+    /// when one is declared.
+    fn expand_traced_response(&self) -> TokenStream {
+        let needed = self
+            .input
+            .endpoints
+            .iter()
+            .any(|def| def.res_format == ResFormat::Traced);
+        if !needed {
+            return quote! {};
+        }
+
+        let traced_response = traced_response_name(&self.input.struct_name);
+        quote! {
+            #[derive(Debug, Clone)]
+            pub struct #traced_response<T> {
+                /// The deserialized response body.
+                pub body: T,
+                /// Final URL of the response, after redirects.
+                pub url: String,
+                /// HTTP status code of the response.
+                pub status: u16,
+                /// Time from sending the request until the response headers arrived.
+                pub elapsed: std::time::Duration,
+            }
+        }
+    }
+
+    /// Expands the struct of an entry in a GraphQL response's `errors` array.
+    fn expand_graphql_error(&self, graphql_error: &Ident) -> TokenStream {
+        quote! {
+            #[derive(Debug, Clone, serde::Deserialize)]
+            pub struct #graphql_error {
+                /// Description of the error.
+                pub message: String,
+                /// Path of the response field that failed, if any.
+                #[serde(default)]
+                pub path: Vec<serde_json::Value>,
+                /// Additional, server-specific error information.
+                #[serde(default)]
+                pub extensions: Option<serde_json::Value>,
+            }
+        }
+    }
+
+    /// Expands the enum returned by content-negotiated endpoints, only when an
+    /// endpoint uses `res_format: json_or_text`.
+    fn expand_json_or_text(&self) -> TokenStream {
+        let needed = self
+            .input
+            .endpoints
+            .iter()
+            .any(|def| def.res_format == ResFormat::JsonOrText);
+        if !needed {
+            return quote! {};
+        }
+
+        let json_or_text = json_or_text_name(&self.input.struct_name);
+        quote! {
+            #[derive(Debug, Clone, PartialEq, Eq)]
+            pub enum #json_or_text<T> {
+                /// A JSON response, deserialized into the endpoint's `res` type.
+                Json(T),
+                /// Any other response, as text.
+                Text(String),
+            }
+        }
+    }
+
+    /// Expands the event, parser and stream types of server-sent events, only
+    /// when an endpoint uses `res_format: sse`.
+    fn expand_sse(&self, error_name: &Ident) -> TokenStream {
+        let needed = self
+            .input
+            .endpoints
+            .iter()
+            .any(|def| def.res_format == ResFormat::Sse);
+        if !needed {
+            return quote! {};
+        }
+
+        let sse_event = sse_event_name(&self.input.struct_name);
+        let sse_parser = sse_parser_name(&self.input.struct_name);
+        let sse_stream = sse_stream_name(&self.input.struct_name);
         quote! {
-            pub struct #struct_name {
+            /// An event of a `text/event-stream` response.
+            #[derive(Debug, Clone, PartialEq, Eq)]
+            pub struct #sse_event {
+                /// The `event:` type, `None` for the default `message` type.
+                pub event: Option<String>,
+                /// The `data:` lines of the event, joined with `\n`.
+                pub data: String,
+            }
+
+            /// The events of a `text/event-stream` response, in order.
+            pub type #sse_stream = std::pin::Pin<
+                Box<dyn futures::Stream<Item = Result<#sse_event, #error_name>> + Send>,
+            >;
+
+            /// Parses `text/event-stream` chunks into events, keeping incomplete
+            /// lines and events until the chunks completing them arrive.
+            #[doc(hidden)]
+            #[derive(Default)]
+            pub struct #sse_parser {
+                buffer: Vec<u8>,
+                event: Option<String>,
+                data: Option<String>,
+            }
+
+            impl #sse_parser {
+                /// Returns the events completed by `chunk`.
+                pub fn feed(&mut self, chunk: &[u8]) -> Vec<#sse_event> {
+                    self.buffer.extend_from_slice(chunk);
+                    let mut events = Vec::new();
+                    while let Some(end) = self.buffer.iter().position(|&b| b == b'\n') {
+                        let line: Vec<u8> = self.buffer.drain(..=end).collect();
+                        let line = String::from_utf8_lossy(&line);
+                        let line = line.trim_end_matches(['\n', '\r']);
+                        // A blank line dispatches the event; one without data is dropped.
+                        if line.is_empty() {
+                            let event = self.event.take();
+                            if let Some(data) = self.data.take() {
+                                events.push(#sse_event { event, data });
+                            }
+                            continue;
+                        }
+                        let (field, value) = line.split_once(':').unwrap_or((line, ""));
+                        let value = value.strip_prefix(' ').unwrap_or(value);
+                        match field {
+                            "event" => self.event = Some(value.to_string()),
+                            "data" => match self.data {
+                                Some(ref mut data) => {
+                                    data.push('\n');
+                                    data.push_str(value);
+                                }
+                                None => self.data = Some(value.to_string()),
+                            },
+                            // Comments, `id:` and `retry:` are not surfaced.
+                            _ => {}
+                        }
+                    }
+                    events
+                }
+            }
+        }
+    }
+
+    /// Expands the enum used to pick a body format at runtime, only when an
+    /// endpoint declares a list of formats.
+    fn expand_body_format(&self) -> TokenStream {
+        let needed = self
+            .input
+            .endpoints
+            .iter()
+            .any(|def| !def.runtime_body_formats.is_empty());
+        if !needed {
+            return quote! {};
+        }
+
+        let body_format = body_format_name(&self.input.struct_name);
+        let apply_blocking = if self.input.options.both {
+            quote! {
+                /// Encodes `body` onto a blocking `request` in this format.
+                pub fn apply_blocking<T: serde::Serialize + ?Sized>(
+                    self,
+                    request: reqwest::blocking::RequestBuilder,
+                    body: &T,
+                ) -> reqwest::blocking::RequestBuilder {
+                    match self {
+                        Self::Json => request.json(body),
+                        Self::Form => request.form(body),
+                    }
+                }
+            }
+        } else {
+            quote! {}
+        };
+
+        quote! {
+            #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+            pub enum #body_format {
+                Json,
+                Form,
+            }
+
+            impl #body_format {
+                /// Encodes `body` onto `request` in this format.
+                pub fn apply<T: serde::Serialize + ?Sized>(
+                    self,
+                    request: reqwest::RequestBuilder,
+                    body: &T,
+                ) -> reqwest::RequestBuilder {
+                    match self {
+                        Self::Json => request.json(body),
+                        Self::Form => request.form(body),
+                    }
+                }
+
+                #apply_blocking
+            }
+        }
+    }
+
+    fn expand_trait_def(
+        &self,
+        error_name: &Ident,
+        flavor: ClientFlavor,
+    ) -> MacroResult<TokenStream> {
+        let trait_name = self.trait_name(flavor);
+        TraitExpander::new(&self.input, &trait_name, error_name, flavor).expand()
+    }
+
+    fn expand_methods(
+        &self,
+        error_name: &Ident,
+        flavor: ClientFlavor,
+    ) -> MacroResult<Vec<TokenStream>> {
+        self.input
+            .endpoints
+            .iter()
+            .map(|def| MethodExpander::new(def, &self.input, error_name, flavor).expand())
+            .collect()
+    }
+
+    fn expand_variants(
+        &self,
+        error_name: &Ident,
+        flavor: ClientFlavor,
+    ) -> MacroResult<Vec<TokenStream>> {
+        self.input
+            .endpoints
+            .iter()
+            .map(|def| MethodExpander::new(def, &self.input, error_name, flavor).expand_variants())
+            .collect()
+    }
+
+    fn expand_struct_impl(
+        &self,
+        error_name: &Ident,
+        flavor: ClientFlavor,
+        methods: &[TokenStream],
+        variants: &[TokenStream],
+    ) -> TokenStream {
+        let struct_name = self.struct_name(flavor);
+        let trait_name = self.trait_name(flavor);
+        let client = self.expand_client(flavor);
+        let client_builder = self.expand_client_builder(flavor);
+        let module = flavor.module();
+        let vis = self.input.options.struct_vis();
+        let (rate_limiter_field, rate_limiter_init) = self.expand_rate_limiter();
+        let (secret_fields, secret_inits, secret_setters) = self.expand_signing_secrets();
+        let endpoint_info = self.endpoint_info_name();
+        let endpoint_infos = self.expand_endpoint_infos();
+        let method_accessors = self.input.endpoints.iter().map(|def| {
+            MethodExpander::new(def, &self.input, error_name, flavor).expand_method_accessor()
+        });
+        let default_timeout = self.expand_default_timeout();
+        let static_paths = self
+            .input
+            .endpoints
+            .iter()
+            .filter(|def| has_static_url(def))
+            .map(|def| {
+                let path = &def.path;
+                match def.host {
+                    Some(ref host) => quote! {
+                        reqwest::Url::parse(#host).and_then(|host| host.join(#path))
+                    },
+                    None => quote! { url.join(#path) },
+                }
+            });
+        let from_env = self.expand_from_env(error_name);
+        let into_dyn = self.expand_into_dyn(&trait_name, flavor);
+        let ext_name = self.ext_name();
+        let state = self.expand_state(&struct_name);
+        let StateTokens {
+            decl_generics,
+            impl_generics,
+            ty_generics,
+            field: state_field,
+            init: state_init,
+            methods: state_methods,
+        } = &state;
+        let mut trait_bounds = Vec::new();
+        if let (Some(state), true) = (&self.input.state, self.input.options.boxed_futures) {
+            // Boxed futures are `Send` and borrow the provider, state included
+            trait_bounds.push(quote! { #state: Sync });
+        }
+        let (trait_impl_generics, trait_ty_generics) = if self.input.options.generic_error {
+            let send = if self.input.options.boxed_futures {
+                quote! { + Send }
+            } else {
+                quote! {}
+            };
+            trait_bounds.push(quote! { E: From<#error_name> #send });
+            let state = self.input.state.iter();
+            (quote! { <#(#state,)* E> }, quote! { <E> })
+        } else {
+            (impl_generics.clone(), quote! {})
+        };
+        let trait_bounds = if trait_bounds.is_empty() {
+            quote! {}
+        } else {
+            quote! { where #(#trait_bounds),* }
+        };
+        quote! {
+            #[derive(Clone)]
+            #vis struct #struct_name #decl_generics {
                 url: reqwest::Url,
-                client: reqwest::Client,
+                client: #module::Client,
                 timeout: std::time::Duration,
+                on_response: Option<std::sync::Arc<dyn Fn(&#module::Response) + Send + Sync>>,
+                interceptor: Option<std::sync::Arc<dyn Fn(#module::Request) -> #module::Request + Send + Sync>>,
+                static_urls: std::sync::Arc<[Result<reqwest::Url, String>]>,
+                header_overrides: Vec<(String, String)>,
+                query_overrides: Vec<(String, String)>,
+                #rate_limiter_field
+                #(#secret_fields)*
+                #state_field
             }
 
             impl #struct_name {
                 pub fn new(url: reqwest::Url, timeout: Option<u64>) -> Self {
-                    let client = reqwest::Client::new();
-                    let timeout = std::time::Duration::from_millis(timeout.unwrap_or(5000));
-                    Self { url, client, timeout }
+                    Self::from_client(url, timeout, #client)
+                }
+
+                /// Creates the provider with a client built from `configure`
+                /// applied to a fresh `ClientBuilder`, e.g. to set a proxy or
+                /// default headers.
+                pub fn new_with(
+                    url: reqwest::Url,
+                    timeout: Option<u64>,
+                    configure: impl FnOnce(#module::ClientBuilder) -> #module::ClientBuilder,
+                ) -> Result<Self, #error_name> {
+                    let client = configure(#client_builder).build()?;
+                    Ok(Self::from_client(url, timeout, client))
+                }
+
+                #from_env
+
+                fn from_client(url: reqwest::Url, timeout: Option<u64>, client: #module::Client) -> Self {
+                    let timeout = std::time::Duration::from_millis(#default_timeout);
+                    // The URLs of endpoints without path parameters never change,
+                    // so they are joined once rather than on every call.
+                    let static_urls = vec![
+                        #(#static_paths.map_err(|e| e.to_string())),*
+                    ]
+                    .into();
+                    Self {
+                        url,
+                        client,
+                        timeout,
+                        on_response: None,
+                        interceptor: None,
+                        static_urls,
+                        header_overrides: Vec::new(),
+                        query_overrides: Vec::new(),
+                        #rate_limiter_init
+                        #(#secret_inits)*
+                        #state_init
+                    }
+                }
+
+                /// Describes every endpoint of the provider, in declaration order.
+                pub fn endpoints() -> Vec<#endpoint_info> {
+                    #[allow(unused_mut)]
+                    let mut endpoints = Vec::new();
+                    #(#endpoint_infos)*
+                    endpoints
+                }
+
+                #(#method_accessors)*
+            }
+
+            impl #impl_generics #struct_name #ty_generics {
+                #state_methods
+
+                #(#secret_setters)*
+
+                #into_dyn
+
+                /// Returns a clone of the provider using `timeout` for every call.
+                /// The clone shares the client and its connection pool.
+                pub fn with_timeout(&self, timeout: std::time::Duration) -> Self
+                where
+                    Self: Clone,
+                {
+                    Self {
+                        timeout,
+                        ..self.clone()
+                    }
+                }
+
+                /// Sets an interceptor applied to every fully built request right
+                /// before it is executed, e.g. to sign it over its method, URL,
+                /// headers and body.
+                pub fn with_request_interceptor(
+                    mut self,
+                    interceptor: impl Fn(#module::Request) -> #module::Request + Send + Sync + 'static,
+                ) -> Self {
+                    self.interceptor = Some(std::sync::Arc::new(interceptor));
+                    self
+                }
+
+                /// Sets a callback invoked with every response right after it is
+                /// received, before the status check and deserialization.
+                ///
+                /// The callback only borrows the response, so it can inspect the
+                /// status and headers but cannot read or consume the body. It runs
+                /// synchronously on the request task and should not block.
+                pub fn with_response_hook(
+                    mut self,
+                    hook: impl Fn(&#module::Response) + Send + Sync + 'static,
+                ) -> Self {
+                    self.on_response = Some(std::sync::Arc::new(hook));
+                    self
+                }
+
+                #(#variants)*
+            }
+
+            // `TryFrom<reqwest::Url>` comes from the blanket impl over `From`.
+            impl From<reqwest::Url> for #struct_name {
+                fn from(url: reqwest::Url) -> Self {
+                    Self::new(url, None)
+                }
+            }
+
+            impl std::str::FromStr for #struct_name {
+                type Err = #error_name;
+
+                fn from_str(s: &str) -> Result<Self, Self::Err> {
+                    let url = reqwest::Url::parse(s)
+                        .map_err(|e| #error_name::UrlConstruction(e.to_string()))?;
+                    Ok(Self::new(url, None))
+                }
+            }
+
+            impl TryFrom<&str> for #struct_name {
+                type Error = #error_name;
+
+                fn try_from(s: &str) -> Result<Self, Self::Error> {
+                    s.parse()
                 }
             }
 
-            impl #trait_name for #struct_name {
+            impl #impl_generics std::fmt::Debug for #struct_name #ty_generics {
+                fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                    // A password in the URL, e.g. from `from_env`, is not printed.
+                    let mut url = self.url.clone();
+                    if url.password().is_some() {
+                        let _ = url.set_password(Some("***"));
+                    }
+                    f.debug_struct(stringify!(#struct_name))
+                        .field("url", &url.as_str())
+                        .field("timeout", &self.timeout)
+                        .finish_non_exhaustive()
+                }
+            }
+
+            #[allow(private_interfaces)]
+            impl #trait_impl_generics #trait_name #trait_ty_generics for #struct_name #ty_generics #trait_bounds {
                 #(#methods)*
             }
+
+            impl #impl_generics #ext_name for #struct_name #ty_generics
+            where
+                Self: Clone,
+            {
+                fn with_header(&self, name: impl Into<String>, value: impl Into<String>) -> Self {
+                    let mut provider = self.clone();
+                    provider.header_overrides.push((name.into(), value.into()));
+                    provider
+                }
+
+                fn with_query(&self, key: impl Into<String>, value: impl Into<String>) -> Self {
+                    let mut provider = self.clone();
+                    provider.query_overrides.push((key.into(), value.into()));
+                    provider
+                }
+            }
+        }
+    }
+
+    /// Expands the generics, field and accessors of the provider's state type
+    /// parameter. Constructors build a provider with `()` state, which is the
+    /// parameter's default, and `with_state` swaps in the actual state.
+    fn expand_state(&self, struct_name: &Ident) -> StateTokens {
+        let Some(ref state) = self.input.state else {
+            return StateTokens::default();
+        };
+
+        let (rate_limiter_field, _) = self.expand_rate_limiter();
+        let rate_limiter = if rate_limiter_field.is_empty() {
+            quote! {}
+        } else {
+            quote! { rate_limiter: self.rate_limiter, }
+        };
+        let secrets = self
+            .input
+            .endpoints
+            .iter()
+            .filter_map(|def| {
+                def.sign_body
+                    .as_ref()
+                    .map(|sign_body| &sign_body.secret_field)
+            })
+            .fold(Vec::new(), |mut fields: Vec<&Ident>, field| {
+                if !fields.contains(&field) {
+                    fields.push(field);
+                }
+                fields
+            });
+        StateTokens {
+            decl_generics: quote! { <#state = ()> },
+            impl_generics: quote! { <#state> },
+            ty_generics: quote! { <#state> },
+            field: quote! { state: #state, },
+            init: quote! { state: (), },
+            methods: quote! {
+                /// Returns the provider's state.
+                pub fn state(&self) -> &#state {
+                    &self.state
+                }
+
+                /// Returns the provider carrying `state`, keeping its client,
+                /// settings and hooks.
+                pub fn with_state<T>(self, state: T) -> #struct_name<T> {
+                    #struct_name {
+                        url: self.url,
+                        client: self.client,
+                        timeout: self.timeout,
+                        on_response: self.on_response,
+                        interceptor: self.interceptor,
+                        static_urls: self.static_urls,
+                        header_overrides: self.header_overrides,
+                        query_overrides: self.query_overrides,
+                        #rate_limiter
+                        #(#secrets: self.#secrets,)*
+                        state,
+                    }
+                }
+            },
+        }
+    }
+
+    /// Expands the timeout in milliseconds used by the constructors. Without an
+    /// explicit timeout, a `timeout_env` variable that is set and parses as an
+    /// integer wins over the 5 second default.
+    fn expand_default_timeout(&self) -> TokenStream {
+        match self.input.options.timeout_env {
+            Some(ref var) => quote! {
+                timeout
+                    .or_else(|| std::env::var(#var).ok()?.trim().parse::<u64>().ok())
+                    .unwrap_or(5000)
+            },
+            None => quote! { timeout.unwrap_or(5000) },
+        }
+    }
+
+    /// Expands `from_env`, reading the base URL and an optional bearer token
+    /// from `{PREFIX}_BASE_URL` and `{PREFIX}_TOKEN`.
+    fn expand_from_env(&self, error_name: &Ident) -> TokenStream {
+        let prefix = self.input.options.env_prefix.as_ref().map_or_else(
+            || self.input.struct_name.to_string().to_shouty_snake_case(),
+            LitStr::value,
+        );
+        let url_var = format!("{}_BASE_URL", prefix);
+        let token_var = format!("{}_TOKEN", prefix);
+        let doc = format!(
+            " Creates the provider from the `{}` environment variable, sending\n \
+             the `{}` variable, when set, as a bearer token with every request.",
+            url_var, token_var
+        );
+        quote! {
+            #[doc = #doc]
+            /// The timeout is the default one, and the token is marked sensitive
+            /// so it is not printed in debug output.
+            pub fn from_env() -> Result<Self, #error_name> {
+                let base_url = std::env::var(#url_var)
+                    .map_err(|e| #error_name::Config(format!("{}: {}", #url_var, e)))?;
+                let url = reqwest::Url::parse(&base_url)
+                    .map_err(|e| #error_name::Config(format!("{}: {}", #url_var, e)))?;
+                let token = match std::env::var(#token_var) {
+                    Ok(token) => {
+                        let mut value =
+                            reqwest::header::HeaderValue::from_str(&format!("Bearer {}", token))
+                                .map_err(|e| #error_name::Config(format!("{}: {}", #token_var, e)))?;
+                        value.set_sensitive(true);
+                        Some(value)
+                    }
+                    Err(std::env::VarError::NotPresent) => None,
+                    Err(e) => return Err(#error_name::Config(format!("{}: {}", #token_var, e))),
+                };
+                Self::new_with(url, None, |builder| match token {
+                    Some(token) => {
+                        let mut headers = reqwest::header::HeaderMap::new();
+                        headers.insert(reqwest::header::AUTHORIZATION, token);
+                        builder.default_headers(headers)
+                    }
+                    None => builder,
+                })
+            }
         }
     }
 
-    fn trait_name(&self) -> Ident {
-        Ident::new(
-            &format!("{}Trait", self.input.struct_name),
-            self.input.struct_name.span(),
+    /// Expands `into_dyn` for traits that are object-safe, which the async
+    /// trait only is with `boxed_futures`.
+    fn expand_into_dyn(&self, trait_name: &Ident, flavor: ClientFlavor) -> TokenStream {
+        if flavor == ClientFlavor::Async && !self.input.options.boxed_futures {
+            return quote! {};
+        }
+
+        quote! {
+            /// Boxes the provider as a trait object, e.g. to store it or a mock
+            /// in the same field.
+            pub fn into_dyn(self) -> Box<dyn #trait_name + Send + Sync>
+            where
+                Self: #trait_name + Send + Sync + 'static,
+            {
+                Box::new(self)
+            }
+        }
+    }
+
+    /// Expands the `reqwest::Client` construction, only going through the
+    /// builder when a provider option needs it.
+    fn expand_client(&self, flavor: ClientFlavor) -> TokenStream {
+        let module = flavor.module();
+        if self.client_settings().is_empty() {
+            return quote! { #module::Client::new() };
+        }
+
+        let client_builder = self.expand_client_builder(flavor);
+        quote! {
+            #client_builder
+                .build()
+                .expect("failed to build reqwest client")
+        }
+    }
+
+    /// Expands a `ClientBuilder` with the provider options already applied.
+    fn expand_client_builder(&self, flavor: ClientFlavor) -> TokenStream {
+        let module = flavor.module();
+        let settings = self.client_settings();
+        quote! {
+            #module::Client::builder()
+                #(#settings)*
+        }
+    }
+
+    fn client_settings(&self) -> Vec<TokenStream> {
+        let options = &self.input.options;
+        let mut settings = Vec::new();
+
+        if let Some(max) = options.pool_max_idle_per_host {
+            settings.push(quote! { .pool_max_idle_per_host(#max) });
+        }
+        if let Some(ms) = options.pool_idle_timeout {
+            settings.push(quote! { .pool_idle_timeout(std::time::Duration::from_millis(#ms)) });
+        }
+        for (domain, addr) in &options.resolve {
+            settings.push(quote! {
+                .resolve(
+                    #domain,
+                    #addr
+                        .parse::<std::net::SocketAddr>()
+                        .expect("address checked by http_provider!"),
+                )
+            });
+        }
+
+        settings
+    }
+
+    /// Expands the rate limiter field and its initializer, only when the
+    /// provider declares a `rate_limit`.
+    fn expand_rate_limiter(&self) -> (TokenStream, TokenStream) {
+        let Some(rate_limit) = self.input.options.rate_limit else {
+            return (quote! {}, quote! {});
+        };
+
+        let count = rate_limit.count;
+        let quota = match rate_limit.period {
+            RatePeriod::Second => quote! { per_second },
+            RatePeriod::Minute => quote! { per_minute },
+            RatePeriod::Hour => quote! { per_hour },
+        };
+        (
+            quote! {
+                rate_limiter: std::sync::Arc<governor::DefaultDirectRateLimiter>,
+            },
+            quote! {
+                rate_limiter: std::sync::Arc::new(governor::RateLimiter::direct(
+                    governor::Quota::#quota(
+                        std::num::NonZeroU32::new(#count).expect("rate_limit count is non-zero"),
+                    ),
+                )),
+            },
         )
     }
 
+    /// Expands the field, initializer and setter of every distinct secret
+    /// named by an endpoint's `sign_body`.
+    fn expand_signing_secrets(&self) -> (Vec<TokenStream>, Vec<TokenStream>, Vec<TokenStream>) {
+        let mut secret_fields: Vec<&Ident> = Vec::new();
+        for def in &self.input.endpoints {
+            if let Some(ref sign_body) = def.sign_body {
+                if !secret_fields.contains(&&sign_body.secret_field) {
+                    secret_fields.push(&sign_body.secret_field);
+                }
+            }
+        }
+
+        let fields = secret_fields
+            .iter()
+            .map(|field| quote! { #field: Option<Vec<u8>>, })
+            .collect();
+        let inits = secret_fields
+            .iter()
+            .map(|field| quote! { #field: None, })
+            .collect();
+        let setters = secret_fields
+            .iter()
+            .map(|field| {
+                let setter = Ident::new(&format!("with_{}", field), field.span());
+                quote! {
+                    /// Sets the HMAC secret used to sign request bodies.
+                    pub fn #setter(mut self, secret: impl Into<Vec<u8>>) -> Self {
+                        self.#field = Some(secret.into());
+                        self
+                    }
+                }
+            })
+            .collect();
+
+        (fields, inits, setters)
+    }
+
+    fn endpoint_info_name(&self) -> Ident {
+        let struct_name = &self.input.struct_name;
+        Ident::new(&format!("{}EndpointInfo", struct_name), struct_name.span())
+    }
+
+    fn ext_name(&self) -> Ident {
+        let struct_name = &self.input.struct_name;
+        Ident::new(&format!("{}Ext", struct_name), struct_name.span())
+    }
+
+    fn struct_name(&self, flavor: ClientFlavor) -> Ident {
+        let struct_name = &self.input.struct_name;
+        match flavor {
+            ClientFlavor::Async => struct_name.clone(),
+            ClientFlavor::Blocking => {
+                Ident::new(&format!("{}Blocking", struct_name), struct_name.span())
+            }
+        }
+    }
+
+    fn trait_name(&self, flavor: ClientFlavor) -> Ident {
+        let struct_name = self.struct_name(flavor);
+        Ident::new(&format!("{}Trait", struct_name), struct_name.span())
+    }
+
+    /// Span errors about an endpoint point at: its path, or the struct name
+    /// for an endpoint without one.
+    fn def_span(&self, def: &EndpointDef) -> proc_macro2::Span {
+        def.path
+            .as_ref()
+            .map_or_else(|| self.input.struct_name.span(), |p| p.span())
+    }
+
+    /// Rejects the first of the `(option, set)` pairs that is set when `both`
+    /// also expands a blocking provider.
+    fn validate_async_only(&self, async_only: &[(&'static str, bool)]) -> MacroResult<()> {
+        if !self.input.options.both {
+            return Ok(());
+        }
+        match async_only.iter().find(|(_, set)| *set) {
+            Some(&(field, _)) => Err(MacroError::IncompatibleFields {
+                span: self.input.struct_name.span(),
+                field,
+                other: "both",
+            }),
+            None => Ok(()),
+        }
+    }
+
     fn validate(&self) -> MacroResult<()> {
         if self.input.endpoints.is_empty() {
             return Err(MacroError::NoEndpointsConfigured {
                 span: self.input.struct_name.span(),
             });
         }
+
+        // Options the blocking client cannot honour: the timers and the
+        // cancellation token are tokio's, and governor only offers an async
+        // wait for a permit.
+        let options = &self.input.options;
+        self.validate_async_only(&[
+            ("deadline", options.deadline),
+            ("total_timeout_ms", options.total_timeout_ms.is_some()),
+            ("cancellation", options.cancellation),
+            ("rate_limit", options.rate_limit.is_some()),
+        ])?;
+
+        for def in &self.input.endpoints {
+            if let Some(ref req) = def.req {
+                if self.input.options.strict && def.method.is_bodyless() {
+                    return Err(MacroError::UnexpectedRequestBody {
+                        span: req.span(),
+                        method: def.method.verb(),
+                    });
+                }
+            }
+            // Streaming bodies are `reqwest::Body`, the `file` argument of a
+            // multipart body is an async `reqwest::multipart::Part` and event
+            // streams are async, so the blocking client has none of them.
+            self.validate_async_only(&[
+                ("body_format: stream", def.body_format == BodyFormat::Stream),
+                (
+                    "body_format: multipart",
+                    def.body_format == BodyFormat::Multipart,
+                ),
+                ("res_format: sse", def.res_format == ResFormat::Sse),
+            ])?;
+            if let Some(ref path) = def.path {
+                if path.value().is_empty() {
+                    return Err(MacroError::InvalidPath { span: path.span() });
+                }
+            }
+            if let Some(ref host) = def.host {
+                let reason = match url::Url::parse(&host.value()) {
+                    Ok(url) if !matches!(url.scheme(), "http" | "https") => {
+                        Some(format!("`{}` is not an HTTP scheme", url.scheme()))
+                    }
+                    Ok(url) if !url.has_host() => Some("the URL has no host".to_string()),
+                    Ok(_) => None,
+                    Err(e) => Some(e.to_string()),
+                };
+                if let Some(reason) = reason {
+                    return Err(MacroError::InvalidHost {
+                        span: host.span(),
+                        reason,
+                    });
+                }
+            }
+            if let (ResFormat::Status, Some(res)) = (def.res_format, &def.res) {
+                return Err(MacroError::IncompatibleFields {
+                    span: res.span(),
+                    field: "res",
+                    other: "res_format: status",
+                });
+            }
+            if let (ResFormat::ToWriter, Some(res)) = (def.res_format, &def.res) {
+                return Err(MacroError::IncompatibleFields {
+                    span: res.span(),
+                    field: "res",
+                    other: "res_format: to_writer",
+                });
+            }
+            if let (ResFormat::Headers, Some(res)) = (def.res_format, &def.res) {
+                return Err(MacroError::IncompatibleFields {
+                    span: res.span(),
+                    field: "res",
+                    other: "res_format: headers",
+                });
+            }
+            if let (ResFormat::Sse, Some(res)) = (def.res_format, &def.res) {
+                return Err(MacroError::IncompatibleFields {
+                    span: res.span(),
+                    field: "res",
+                    other: "res_format: sse",
+                });
+            }
+            if !def.default_on_status.is_empty() {
+                let other = match def.res_format {
+                    ResFormat::Status => Some("res_format: status"),
+                    ResFormat::Traced => Some("res_format: traced"),
+                    ResFormat::JsonOrText => Some("res_format: json_or_text"),
+                    ResFormat::WithStatus => Some("res_format: with_status"),
+                    ResFormat::WithBytes => Some("res_format: with_bytes"),
+                    ResFormat::Sse => Some("res_format: sse"),
+                    ResFormat::ToWriter => Some("res_format: to_writer"),
+                    ResFormat::Headers => Some("res_format: headers"),
+                    ResFormat::Json | ResFormat::Auto => None,
+                };
+                if let Some(other) = other {
+                    return Err(MacroError::IncompatibleFields {
+                        span: self.def_span(def),
+                        field: "default_on_status",
+                        other,
+                    });
+                }
+            }
+            if def.typed_headers && def.headers.is_none() {
+                return Err(MacroError::RequiresHeaders {
+                    span: self.def_span(def),
+                    field: "typed_headers",
+                });
+            }
+            if def.req.is_none() {
+                if let Some(ref validate_with) = def.validate_with {
+                    return Err(MacroError::RequiresRequestBody {
+                        span: validate_with.span(),
+                        field: "validate_with",
+                    });
+                }
+                if let Some(ref sign_body) = def.sign_body {
+                    return Err(MacroError::RequiresRequestBody {
+                        span: sign_body.header.span(),
+                        field: "sign_body",
+                    });
+                }
+                if def.body_format == BodyFormat::Multipart {
+                    return Err(MacroError::RequiresRequestBody {
+                        span: self.def_span(def),
+                        field: "body_format: multipart",
+                    });
+                }
+            }
+            if def.batch {
+                self.validate_batch(def)?;
+            }
+            if def.paginate.is_some() {
+                self.validate_paginate(def)?;
+            }
+            if let (
+                true,
+                Some(MapFn {
+                    with,
+                    output: Some(_),
+                }),
+            ) = (self.input.options.generic_error, &def.map_err)
+            {
+                return Err(MacroError::IncompatibleFields {
+                    span: with.span(),
+                    field: "map_err -> Type",
+                    other: "generic_error",
+                });
+            }
+            for (enabled, field) in [(def.measure, "measure"), (def.location, "location")] {
+                let other = match def.res_format {
+                    _ if !enabled => None,
+                    _ if def.paginate.is_some() => Some("paginate"),
+                    ResFormat::Sse => Some("res_format: sse"),
+                    ResFormat::ToWriter => Some("res_format: to_writer"),
+                    _ => None,
+                };
+                if let Some(other) = other {
+                    return Err(MacroError::IncompatibleFields {
+                        span: self.def_span(def),
+                        field,
+                        other,
+                    });
+                }
+            }
+            if !def.check_status {
+                let other = if def.status_ok_with.is_some() {
+                    Some("status_ok_with")
+                } else if def.err.is_some() {
+                    Some("err")
+                } else {
+                    None
+                };
+                if let Some(other) = other {
+                    return Err(MacroError::IncompatibleFields {
+                        span: self.def_span(def),
+                        field: "check_status: false",
+                        other,
+                    });
+                }
+            }
+            if let (Some(_), Some(_)) = (def.timeout, def.timeout_mul) {
+                return Err(MacroError::IncompatibleFields {
+                    span: self.def_span(def),
+                    field: "timeout_mul",
+                    other: "timeout",
+                });
+            }
+            if def.graphql && def.res_format != ResFormat::Json {
+                return Err(MacroError::IncompatibleFields {
+                    span: self.def_span(def),
+                    field: "graphql",
+                    other: "a non-JSON res_format",
+                });
+            }
+            if let Some(ref sign_body) = def.sign_body {
+                let secret_field = &sign_body.secret_field;
+                if RESERVED_FIELDS.iter().any(|name| secret_field == name) {
+                    return Err(MacroError::ReservedFieldName {
+                        span: secret_field.span(),
+                        name: secret_field.to_string(),
+                    });
+                }
+                let setter = format!("with_{}", secret_field);
+                if PROVIDER_METHODS.contains(&setter.as_str()) {
+                    return Err(MacroError::ReservedSetterName {
+                        span: secret_field.span(),
+                        setter,
+                    });
+                }
+                if def.body_format != BodyFormat::Json || !def.runtime_body_formats.is_empty() {
+                    return Err(MacroError::IncompatibleFields {
+                        span: sign_body.header.span(),
+                        field: "sign_body",
+                        other: "a non-JSON body_format",
+                    });
+                }
+            }
+        }
+        self.validate_method_names()
+    }
+
+    /// Rejects endpoints whose generated methods, variants included, share a
+    /// name with each other or with the provider's own methods. Inherent
+    /// variants would otherwise silently shadow the trait method of another
+    /// endpoint.
+    fn validate_method_names(&self) -> MacroResult<()> {
+        let mut names: Vec<String> = PROVIDER_METHODS
+            .iter()
+            .map(|name| name.to_string())
+            .collect();
+        if self.input.state.is_some() {
+            names.extend(["state".to_string(), "with_state".to_string()]);
+        }
+        for def in &self.input.endpoints {
+            if let Some(ref sign_body) = def.sign_body {
+                let setter = format!("with_{}", sign_body.secret_field);
+                if !names.contains(&setter) {
+                    names.push(setter);
+                }
+            }
+        }
+
+        for def in &self.input.endpoints {
+            for name in generated_method_names(def, &self.input.options) {
+                if names.contains(&name) {
+                    return Err(MacroError::DuplicateMethod {
+                        span: def
+                            .fn_name
+                            .as_ref()
+                            .map(|fn_name| fn_name.span())
+                            .or_else(|| def.path.as_ref().map(|path| path.span()))
+                            .unwrap_or_else(|| self.input.struct_name.span()),
+                        name,
+                    });
+                }
+                names.push(name);
+            }
+        }
+        Ok(())
+    }
+
+    /// Pages are deserialized into `res`, so its format must be plain JSON.
+    /// The arguments of `*_all` are cloned for every page, so the body must be
+    /// cloneable too.
+    fn validate_paginate(&self, def: &EndpointDef) -> MacroResult<()> {
+        let span = self.def_span(def);
+        if def.res.is_none() {
+            return Err(MacroError::RequiresResponseBody {
+                span,
+                field: "paginate",
+            });
+        }
+        // A page is never `None`, and `*_all` clones the arguments for every
+        // page, which a `reqwest::Body` or multipart `Part` cannot be.
+        let other = if def.res_format != ResFormat::Json {
+            Some("a non-JSON res_format")
+        } else if def
+            .res
+            .as_ref()
+            .is_some_and(|res| option_inner(res).is_some())
+        {
+            Some("an Option res")
+        } else if def.body_format == BodyFormat::Stream {
+            Some("body_format: stream")
+        } else if def.body_format == BodyFormat::Multipart {
+            Some("body_format: multipart")
+        } else if def.batch {
+            Some("batch")
+        } else if def.graphql {
+            Some("graphql")
+        } else {
+            None
+        };
+        match other {
+            Some(other) => Err(MacroError::IncompatibleFields {
+                span,
+                field: "paginate",
+                other,
+            }),
+            None => Ok(()),
+        }
+    }
+
+    /// A batch sends its operations as a JSON array and expects a JSON array
+    /// holding one result per operation.
+    fn validate_batch(&self, def: &EndpointDef) -> MacroResult<()> {
+        let span = self.def_span(def);
+        let Some(ref req) = def.req else {
+            return Err(MacroError::RequiresRequestBody {
+                span,
+                field: "batch",
+            });
+        };
+        let is_vec = |ty: &syn::Type| vec_inner(ty).is_some();
+        if !is_vec(req) || !def.res.as_ref().is_some_and(is_vec) {
+            return Err(MacroError::InvalidBatch { span });
+        }
+        if def.body_format != BodyFormat::Json || !def.runtime_body_formats.is_empty() {
+            return Err(MacroError::IncompatibleFields {
+                span,
+                field: "batch",
+                other: "a non-JSON body_format",
+            });
+        }
+        if def.res_format != ResFormat::Json {
+            return Err(MacroError::IncompatibleFields {
+                span,
+                field: "batch",
+                other: "a non-JSON res_format",
+            });
+        }
         Ok(())
     }
 }
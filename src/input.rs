@@ -1,15 +1,24 @@
+use crate::expanders::FnNameExpander;
 use syn::{
-    braced,
+    braced, bracketed, parenthesized,
     parse::{Parse, ParseStream, Result},
     punctuated::Punctuated,
-    Ident, LitStr, Token, Type,
+    Ident, Lit, LitBool, LitInt, LitStr, Path, Token, Type, Visibility,
 };
 
 /// Represents HTTP methods supported by the provider macro.
 ///
 /// These methods align with standard HTTP/1.1 methods and are used
 /// to define the type of request for each endpoint.
+///
+/// Methods are matched case-insensitively, so `get`, `Get` and `GET` are all
+/// accepted. Generated function names always use the lowercase form returned
+/// by [`HttpMethod::name`], since names are snake_cased regardless.
+///
+/// Non-standard verbs such as Varnish's `PURGE` are given as `custom("PURGE")`
+/// and sent exactly as written.
 #[derive(Debug, Clone)]
+#[allow(clippy::upper_case_acronyms)]
 pub enum HttpMethod {
     /// HTTP GET method for retrieving resources
     GET,
@@ -22,15 +31,67 @@ pub enum HttpMethod {
 
     /// HTTP DELETE method for removing resources
     DELETE,
+
+    /// HTTP CONNECT method for establishing a tunnel, e.g. through a proxy
+    CONNECT,
+
+    /// HTTP TRACE method echoing the request back for diagnostics
+    TRACE,
+
+    /// Any other method, e.g. `PURGE` or a WebDAV verb. Without a `fn_name`,
+    /// the method must also start a valid function name:
+    ///
+    /// ```compile_fail
+    /// use http_provider_macro::http_provider;
+    ///
+    /// http_provider!(
+    ///     CacheApi,
+    ///     {
+    ///         { path: "/pages", method: custom("1X"), res_format: status, },
+    ///     }
+    /// );
+    /// ```
+    Custom(LitStr),
 }
 
 impl HttpMethod {
-    pub fn as_str(&self) -> &'static str {
+    /// Returns the lowercase method name used as the generated function prefix.
+    pub fn name(&self) -> String {
+        self.verb().to_lowercase()
+    }
+
+    /// Returns the method as sent on the wire, e.g. `GET` or `PURGE`.
+    pub fn verb(&self) -> String {
         match self {
-            HttpMethod::GET => "get",
-            HttpMethod::POST => "post",
-            HttpMethod::PUT => "put",
-            HttpMethod::DELETE => "delete",
+            HttpMethod::GET => "GET".to_string(),
+            HttpMethod::POST => "POST".to_string(),
+            HttpMethod::PUT => "PUT".to_string(),
+            HttpMethod::DELETE => "DELETE".to_string(),
+            HttpMethod::CONNECT => "CONNECT".to_string(),
+            HttpMethod::TRACE => "TRACE".to_string(),
+            HttpMethod::Custom(verb) => verb.value(),
+        }
+    }
+
+    /// Whether repeating a request with this method has the same effect as
+    /// sending it once, so a failed attempt can safely be retried.
+    pub fn is_idempotent(&self) -> bool {
+        match self {
+            HttpMethod::GET | HttpMethod::PUT | HttpMethod::DELETE | HttpMethod::TRACE => true,
+            HttpMethod::Custom(verb) => ["HEAD", "OPTIONS"]
+                .iter()
+                .any(|idempotent| verb.value().eq_ignore_ascii_case(idempotent)),
+            HttpMethod::POST | HttpMethod::CONNECT => false,
+        }
+    }
+
+    /// Whether requests with this method normally have no body, so a `req` is
+    /// likely a mistake.
+    pub fn is_bodyless(&self) -> bool {
+        match self {
+            HttpMethod::GET | HttpMethod::DELETE | HttpMethod::TRACE => true,
+            HttpMethod::Custom(verb) => verb.value().eq_ignore_ascii_case("HEAD"),
+            _ => false,
         }
     }
 }
@@ -50,6 +111,27 @@ impl Parse for HttpMethod {
             "POST" => Ok(HttpMethod::POST),
             "PUT" => Ok(HttpMethod::PUT),
             "DELETE" => Ok(HttpMethod::DELETE),
+            "CONNECT" => Ok(HttpMethod::CONNECT),
+            "TRACE" => Ok(HttpMethod::TRACE),
+            "CUSTOM" if input.peek(syn::token::Paren) => {
+                let content;
+                parenthesized!(content in input);
+                let verb: LitStr = content.parse()?;
+                // A method is an HTTP token; whether it also makes a valid
+                // function name is checked once the path is known.
+                let value = verb.value();
+                if value.is_empty()
+                    || !value
+                        .chars()
+                        .all(|c| c.is_ascii_alphanumeric() || "!#$%&'*+-.^_`|~".contains(c))
+                {
+                    return Err(syn::Error::new(
+                        verb.span(),
+                        "custom HTTP methods must be a non-empty token such as \"PURGE\"",
+                    ));
+                }
+                Ok(HttpMethod::Custom(verb))
+            }
             _ => Err(syn::Error::new(
                 ident.span(),
                 format!("Unsupported HTTP method: {}", ident),
@@ -58,6 +140,394 @@ impl Parse for HttpMethod {
     }
 }
 
+/// Represents how the request body is encoded.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum BodyFormat {
+    /// Serialize the body as JSON (default)
+    #[default]
+    Json,
+
+    /// Pass the body by value to `RequestBuilder::body`, e.g. `reqwest::Body`
+    Stream,
+
+    /// Send a pre-serialized `String` as-is with a JSON content type
+    RawJson,
+
+    /// Serialize the body as `application/x-www-form-urlencoded`
+    Form,
+
+    /// Send a `multipart/form-data` body with the body serialized as a JSON
+    /// `metadata` part and a `reqwest::multipart::Part` argument as `file`
+    Multipart,
+}
+
+impl BodyFormat {
+    /// Whether the body parameter is taken by value rather than by reference.
+    pub fn is_owned(&self) -> bool {
+        matches!(self, BodyFormat::Stream | BodyFormat::RawJson)
+    }
+
+    /// Whether the format serializes a typed body with serde, which makes it
+    /// selectable at runtime.
+    pub fn is_serialized(&self) -> bool {
+        matches!(self, BodyFormat::Json | BodyFormat::Form)
+    }
+
+    fn from_ident(ident: &Ident) -> Result<Self> {
+        match ident.to_string().as_str() {
+            "json" => Ok(BodyFormat::Json),
+            "stream" => Ok(BodyFormat::Stream),
+            "raw_json" => Ok(BodyFormat::RawJson),
+            "form" => Ok(BodyFormat::Form),
+            "multipart" => Ok(BodyFormat::Multipart),
+            _ => Err(syn::Error::new(
+                ident.span(),
+                format!("Unsupported body format: {}", ident),
+            )),
+        }
+    }
+
+    /// Parses a bracketed list of formats the caller can choose from at
+    /// runtime, e.g. `[json, form]`.
+    fn parse_runtime_list(input: ParseStream) -> Result<Vec<Self>> {
+        let content;
+        let bracket = bracketed!(content in input);
+        let idents = Punctuated::<Ident, Token![,]>::parse_terminated(&content)?;
+
+        let mut formats = Vec::new();
+        for ident in &idents {
+            let format = Self::from_ident(ident)?;
+            if !format.is_serialized() {
+                return Err(syn::Error::new(
+                    ident.span(),
+                    "only `json` and `form` can be selected at runtime",
+                ));
+            }
+            if !formats.contains(&format) {
+                formats.push(format);
+            }
+        }
+
+        if formats.len() < 2 {
+            return Err(syn::Error::new(
+                bracket.span.join(),
+                "runtime body format selection needs at least two formats",
+            ));
+        }
+        Ok(formats)
+    }
+}
+
+impl Parse for BodyFormat {
+    fn parse(input: ParseStream) -> Result<Self> {
+        let ident: Ident = input.parse()?;
+        Self::from_ident(&ident)
+    }
+}
+
+/// Represents how the response is turned into the method's return value.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ResFormat {
+    /// Deserialize the body as JSON into `res`, or return `()` (default)
+    #[default]
+    Json,
+
+    /// Skip the body and return the `reqwest::StatusCode`
+    Status,
+
+    /// Deserialize into `res` only when the `Content-Type` is JSON, and fail
+    /// with a `Deserialization` error naming the actual type otherwise
+    Auto,
+
+    /// Return a `{Name}TracedResponse` pairing the body with the final URL,
+    /// the status and the elapsed time
+    Traced,
+
+    /// Return a `{Name}JsonOrText` holding the body deserialized into `res`
+    /// when the `Content-Type` is JSON, and the raw text otherwise
+    JsonOrText,
+
+    /// Return the body deserialized into `res` paired with the
+    /// `reqwest::StatusCode`
+    WithStatus,
+
+    /// Return the body deserialized into `res` paired with the raw
+    /// `bytes::Bytes` it was parsed from
+    WithBytes,
+
+    /// Return a `{Name}SseStream` of the server-sent events in a
+    /// `text/event-stream` body
+    Sse,
+
+    /// Return the raw body bytes, and generate a `*_to` variant streaming the
+    /// body into a `std::io::Write` instead
+    ToWriter,
+
+    /// Return the `reqwest::header::HeaderMap` of the response without reading
+    /// the body
+    Headers,
+}
+
+impl Parse for ResFormat {
+    fn parse(input: ParseStream) -> Result<Self> {
+        let ident: Ident = input.parse()?;
+        match ident.to_string().as_str() {
+            "json" => Ok(ResFormat::Json),
+            "status" => Ok(ResFormat::Status),
+            "auto" => Ok(ResFormat::Auto),
+            "traced" => Ok(ResFormat::Traced),
+            "json_or_text" => Ok(ResFormat::JsonOrText),
+            "with_status" => Ok(ResFormat::WithStatus),
+            "with_bytes" => Ok(ResFormat::WithBytes),
+            "sse" => Ok(ResFormat::Sse),
+            "to_writer" => Ok(ResFormat::ToWriter),
+            "headers" => Ok(ResFormat::Headers),
+            _ => Err(syn::Error::new(
+                ident.span(),
+                format!("Unsupported response format: {}", ident),
+            )),
+        }
+    }
+}
+
+/// Represents how JSON response bodies are deserialized.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum JsonBackend {
+    /// Plain `serde_json` (default)
+    #[default]
+    Serde,
+
+    /// `serde_path_to_error`, which prefixes failures with the offending field path
+    PathToError,
+}
+
+impl Parse for JsonBackend {
+    fn parse(input: ParseStream) -> Result<Self> {
+        let ident: Ident = input.parse()?;
+        match ident.to_string().as_str() {
+            "serde" => Ok(JsonBackend::Serde),
+            "path_to_error" => Ok(JsonBackend::PathToError),
+            _ => Err(syn::Error::new(
+                ident.span(),
+                format!("Unsupported JSON backend: {}", ident),
+            )),
+        }
+    }
+}
+
+/// A request quota parsed from a string such as `"10/s"`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RateLimit {
+    pub count: u32,
+    pub period: RatePeriod,
+}
+
+/// The period a [`RateLimit`] count applies to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RatePeriod {
+    Second,
+    Minute,
+    Hour,
+}
+
+impl Parse for RateLimit {
+    /// Parses `"<count>/<period>"` where the period is `s`, `m` or `h`.
+    fn parse(input: ParseStream) -> Result<Self> {
+        let lit: LitStr = input.parse()?;
+        let value = lit.value();
+        let invalid = || {
+            syn::Error::new(
+                lit.span(),
+                "rate_limit must look like \"10/s\", with a non-zero count and a period of `s`, `m` or `h`",
+            )
+        };
+
+        let (count, period) = value.split_once('/').ok_or_else(invalid)?;
+        let count = count.trim().parse::<u32>().map_err(|_| invalid())?;
+        if count == 0 {
+            return Err(invalid());
+        }
+        let period = match period.trim() {
+            "s" => RatePeriod::Second,
+            "m" => RatePeriod::Minute,
+            "h" => RatePeriod::Hour,
+            _ => return Err(invalid()),
+        };
+
+        Ok(RateLimit { count, period })
+    }
+}
+
+/// Configuration for signing a request body with HMAC-SHA256.
+///
+/// The secret is stored on the provider in a field named `secret_field` and set
+/// through the generated `with_<secret_field>` method.
+#[derive(Debug, Clone)]
+pub struct SignBody {
+    pub header: LitStr,
+    pub secret_field: Ident,
+}
+
+/// A function an endpoint's results or errors are passed through, as given to
+/// `map_err` and `res_map`.
+///
+/// Without an `output` type the function maps a value to its own type, e.g.
+/// to add context to an error.
+#[derive(Debug, Clone)]
+pub struct MapFn {
+    pub with: syn::Path,
+    pub output: Option<Type>,
+}
+
+impl Parse for MapFn {
+    /// Parses `to_domain` or `to_domain -> DomainError`.
+    fn parse(input: ParseStream) -> Result<Self> {
+        let with = input.parse()?;
+        let output = if input.peek(Token![->]) {
+            input.parse::<Token![->]>()?;
+            Some(input.parse()?)
+        } else {
+            None
+        };
+        Ok(MapFn { with, output })
+    }
+}
+
+/// A typed error body, deserialized from failed responses.
+///
+/// Each distinct type gets a variant of the generated error enum named after
+/// the type, holding the status and the body.
+#[derive(Debug, Clone)]
+pub struct ErrorBody {
+    pub ty: Type,
+    pub variant: Ident,
+    /// Statuses whose body is deserialized; every failure when empty.
+    pub statuses: Vec<u16>,
+}
+
+impl Parse for ErrorBody {
+    /// Parses `ApiError` or `ApiError on [400, 422]`.
+    fn parse(input: ParseStream) -> Result<Self> {
+        let ty: Type = input.parse()?;
+        let variant = match ty {
+            Type::Path(ref path) if path.qself.is_none() => path
+                .path
+                .segments
+                .last()
+                .map(|segment| segment.ident.clone()),
+            _ => None,
+        }
+        .ok_or_else(|| syn::Error::new_spanned(&ty, "`err` must be a named type"))?;
+
+        let mut statuses = Vec::new();
+        if input.peek(Ident) {
+            let on: Ident = input.parse()?;
+            if on != "on" {
+                return Err(syn::Error::new(on.span(), "expected `on [..]`"));
+            }
+            statuses = parse_status_list(input)?;
+        }
+
+        Ok(ErrorBody {
+            ty,
+            variant,
+            statuses,
+        })
+    }
+}
+
+impl Parse for SignBody {
+    /// Parses `{ header: "X-Signature", secret_field: hmac_secret }`.
+    fn parse(input: ParseStream) -> Result<Self> {
+        let content;
+        let brace = braced!(content in input);
+
+        let mut header = None;
+        let mut secret_field = None;
+        while !content.is_empty() {
+            let field: Ident = content.parse()?;
+            content.parse::<Token![:]>()?;
+            match field.to_string().as_str() {
+                "header" => header = Some(content.parse()?),
+                "secret_field" => secret_field = Some(content.parse()?),
+                _ => return Err(syn::Error::new(field.span(), "unexpected sign_body field")),
+            }
+            if content.peek(Token![,]) {
+                content.parse::<Token![,]>()?;
+            }
+        }
+
+        Ok(SignBody {
+            header: header.ok_or_else(|| syn::Error::new(brace.span.join(), "missing `header`"))?,
+            secret_field: secret_field
+                .ok_or_else(|| syn::Error::new(brace.span.join(), "missing `secret_field`"))?,
+        })
+    }
+}
+
+/// Where a paginated endpoint finds the cursor of the next page, and the query
+/// parameter the cursor is sent back in.
+#[derive(Debug, Clone)]
+pub struct Paginate {
+    pub cursor: CursorSource,
+    pub cursor_param: LitStr,
+}
+
+/// Location of the next page's cursor in a response.
+#[derive(Debug, Clone)]
+pub enum CursorSource {
+    /// A top-level field of the JSON body
+    Field(LitStr),
+    /// A response header
+    Header(LitStr),
+}
+
+impl Parse for Paginate {
+    /// Parses `{ cursor_field: "next" }` or `{ cursor_header: "X-Next-Cursor" }`,
+    /// optionally with `cursor_param: "page_token"` (defaults to `"cursor"`).
+    fn parse(input: ParseStream) -> Result<Self> {
+        let content;
+        let brace = braced!(content in input);
+
+        let mut cursor = None;
+        let mut cursor_param = None;
+        while !content.is_empty() {
+            let field: Ident = content.parse()?;
+            content.parse::<Token![:]>()?;
+            let source = match field.to_string().as_str() {
+                "cursor_field" => Some(CursorSource::Field(content.parse()?)),
+                "cursor_header" => Some(CursorSource::Header(content.parse()?)),
+                "cursor_param" => {
+                    cursor_param = Some(content.parse()?);
+                    None
+                }
+                _ => return Err(syn::Error::new(field.span(), "unexpected paginate field")),
+            };
+            if let Some(source) = source {
+                if cursor.replace(source).is_some() {
+                    return Err(syn::Error::new(
+                        field.span(),
+                        "only one of `cursor_field` and `cursor_header` can be given",
+                    ));
+                }
+            }
+            if content.peek(Token![,]) {
+                content.parse::<Token![,]>()?;
+            }
+        }
+
+        Ok(Paginate {
+            cursor: cursor.ok_or_else(|| {
+                syn::Error::new(
+                    brace.span.join(),
+                    "missing `cursor_field` or `cursor_header`",
+                )
+            })?,
+            cursor_param: cursor_param.unwrap_or_else(|| LitStr::new("cursor", brace.span.join())),
+        })
+    }
+}
+
 /// Root structure for parsing the HTTP provider macro input.
 ///
 /// This structure represents the complete macro definition including
@@ -77,10 +547,223 @@ pub struct HttpProviderInput {
     /// Name of the provider struct that will be generated
     pub struct_name: Ident,
 
+    /// Optional state type parameter, as in `ApiClient<S>`
+    pub state: Option<Ident>,
+
+    /// Provider-level options
+    pub options: ProviderOptions,
+
     /// Collection of endpoint definitions
     pub endpoints: Vec<EndpointDef>,
 }
 
+/// Provider-wide configuration given as `key: value` pairs between the
+/// struct name and the endpoint block.
+///
+/// * `pool_max_idle_per_host` - Optional maximum idle connections kept per host
+/// * `pool_idle_timeout` - Optional idle connection timeout in milliseconds
+/// * `resolve` - Optional DNS overrides mapping domains to fixed addresses
+/// * `metrics` - Record request counts and latencies via the `metrics` crate
+///   (requires the `metrics` feature)
+/// * `boxed_futures` - Trait methods return `Pin<Box<dyn Future + Send>>`
+///   instead of being `async fn`, making the trait object-safe
+/// * `deadline` - Generated methods take a trailing
+///   `deadline: Option<tokio::time::Instant>` bounding the whole call
+/// * `cancellation` - Generated methods take a trailing
+///   `cancel: Option<&tokio_util::sync::CancellationToken>` aborting the call
+///   (requires the `tokio-util` feature)
+/// * `json_backend` - `serde` (default) or `path_to_error`, which includes the
+///   failing field path in `Deserialization` errors
+/// * `strict_json` - Fails deserialization when a JSON response has fields the
+///   `res` type does not know (requires the `serde_ignored` feature)
+/// * `struct_vis` - Visibility of the provider struct (defaults to `pub`)
+/// * `trait_vis` - Visibility of the provider trait (defaults to `pub`)
+/// * `trait_bound` - Optional supertraits of the provider trait, e.g.
+///   `Lifecycle + Send`
+/// * `fn_prefix` / `fn_suffix` - Strings added around every method name,
+///   which must keep it a valid identifier
+/// * `rate_limit` - Request quota such as `"10/s"` enforced before every send
+/// * `log_bodies` - Logs JSON request bodies and raw response bodies at trace level
+/// * `request_id` - Send a fresh `X-Request-Id` UUID with every request
+///   (requires the `uuid` feature)
+/// * `correlation_id` - Optional path to a `tokio::task_local!` whose value is
+///   sent as an `X-Correlation-Id` header when set
+/// * `env_prefix` - Prefix of the variables `from_env` reads (defaults to the
+///   struct name in `SCREAMING_SNAKE_CASE`)
+/// * `timeout_env` - Environment variable holding the default timeout in
+///   milliseconds, read when the provider is constructed without a timeout
+/// * `retries` - How many times a request failing to connect, timing out or
+///   getting a 5xx status is sent again
+/// * `retry_backoff_ms` - Optional delay in milliseconds before the first
+///   retry, doubled before every further one
+/// * `total_timeout_ms` - Optional budget in milliseconds for a whole call,
+///   retries included
+/// * `idempotent_only` - Only retry idempotent methods (defaults to `true`)
+/// * `generic_error` - Makes the trait generic over its error type, which any
+///   type implementing `From<{Name}Error>` can be
+/// * `strict` - Rejects a `req` on GET, HEAD, DELETE and TRACE endpoints
+///   instead of warning about it
+/// * `both` - Also generates a `{Name}Blocking` provider and
+///   `{Name}BlockingTrait` built on `reqwest::blocking::Client`
+/// * `test_helpers` - Also generates a `{Name}Mock` implementing the provider
+///   traits with canned responses, under `#[cfg(test)]`
+///
+/// A prefix or suffix that does not keep the method names valid is rejected:
+///
+/// ```compile_fail
+/// use http_provider_macro::http_provider;
+///
+/// http_provider!(
+///     UserApi,
+///     fn_prefix: "users-",
+///     {
+///         { path: "/users", method: GET, res_format: status, },
+///     }
+/// );
+/// ```
+#[derive(Default)]
+pub struct ProviderOptions {
+    pub pool_max_idle_per_host: Option<usize>,
+    pub pool_idle_timeout: Option<u64>,
+    pub resolve: Vec<(LitStr, String)>,
+    pub metrics: bool,
+    pub boxed_futures: bool,
+    pub both: bool,
+    pub deadline: bool,
+    pub cancellation: bool,
+    pub json_backend: JsonBackend,
+    pub strict_json: bool,
+    pub struct_vis: Option<Visibility>,
+    pub trait_vis: Option<Visibility>,
+    pub trait_bound: Vec<syn::TypeParamBound>,
+    pub fn_prefix: Option<LitStr>,
+    pub fn_suffix: Option<LitStr>,
+    pub rate_limit: Option<RateLimit>,
+    pub log_bodies: bool,
+    pub timeout_env: Option<LitStr>,
+    pub env_prefix: Option<LitStr>,
+    pub strict: bool,
+    pub generic_error: bool,
+    pub retries: u32,
+    pub retry_backoff_ms: Option<u64>,
+    pub total_timeout_ms: Option<u64>,
+    /// Set by `idempotent_only: false`, so the default retries idempotent
+    /// methods only.
+    pub retry_non_idempotent: bool,
+    pub request_id: bool,
+    pub correlation_id: Option<syn::Path>,
+    pub test_helpers: bool,
+}
+
+impl ProviderOptions {
+    pub fn struct_vis(&self) -> Visibility {
+        self.struct_vis
+            .clone()
+            .unwrap_or_else(|| syn::parse_quote! { pub })
+    }
+
+    pub fn trait_vis(&self) -> Visibility {
+        self.trait_vis
+            .clone()
+            .unwrap_or_else(|| syn::parse_quote! { pub })
+    }
+    /// Parses options until the opening brace of the endpoint block.
+    fn parse_until_endpoints(input: ParseStream) -> Result<Self> {
+        let mut options = Self::default();
+
+        while !input.peek(syn::token::Brace) {
+            let field: Ident = input.parse()?;
+            input.parse::<Token![:]>()?;
+
+            match field.to_string().as_str() {
+                "pool_max_idle_per_host" => {
+                    options.pool_max_idle_per_host = Some(input.parse::<LitInt>()?.base10_parse()?)
+                }
+                "pool_idle_timeout" => {
+                    options.pool_idle_timeout = Some(input.parse::<LitInt>()?.base10_parse()?)
+                }
+                "metrics" => {
+                    options.metrics = input.parse::<LitBool>()?.value;
+                    if options.metrics {
+                        require_feature(&field, cfg!(feature = "metrics"), "metrics")?;
+                    }
+                }
+                "boxed_futures" => options.boxed_futures = input.parse::<LitBool>()?.value,
+                "both" => options.both = input.parse::<LitBool>()?.value,
+                "test_helpers" => options.test_helpers = input.parse::<LitBool>()?.value,
+                "deadline" => options.deadline = input.parse::<LitBool>()?.value,
+                "cancellation" => {
+                    options.cancellation = input.parse::<LitBool>()?.value;
+                    if options.cancellation {
+                        require_feature(&field, cfg!(feature = "tokio-util"), "tokio-util")?;
+                    }
+                }
+                "struct_vis" => options.struct_vis = Some(input.parse()?),
+                "trait_vis" => options.trait_vis = Some(input.parse()?),
+                "trait_bound" => {
+                    options.trait_bound =
+                        Punctuated::<syn::TypeParamBound, Token![+]>::parse_separated_nonempty(
+                            input,
+                        )?
+                        .into_iter()
+                        .collect()
+                }
+                "fn_prefix" => options.fn_prefix = Some(input.parse()?),
+                "fn_suffix" => options.fn_suffix = Some(input.parse()?),
+                "timeout_env" => options.timeout_env = Some(input.parse()?),
+                "env_prefix" => options.env_prefix = Some(input.parse()?),
+                "resolve" => options.resolve = parse_resolve(input)?,
+                "strict" => options.strict = input.parse::<LitBool>()?.value,
+                "generic_error" => options.generic_error = input.parse::<LitBool>()?.value,
+                "retries" => options.retries = input.parse::<LitInt>()?.base10_parse()?,
+                "retry_backoff_ms" => {
+                    options.retry_backoff_ms = Some(input.parse::<LitInt>()?.base10_parse()?)
+                }
+                "total_timeout_ms" => {
+                    options.total_timeout_ms = Some(input.parse::<LitInt>()?.base10_parse()?)
+                }
+                "idempotent_only" => {
+                    options.retry_non_idempotent = !input.parse::<LitBool>()?.value
+                }
+                "request_id" => {
+                    options.request_id = input.parse::<LitBool>()?.value;
+                    if options.request_id {
+                        require_feature(&field, cfg!(feature = "uuid"), "uuid")?;
+                    }
+                }
+                "correlation_id" => options.correlation_id = Some(input.parse()?),
+                "log_bodies" => {
+                    options.log_bodies = input.parse::<LitBool>()?.value;
+                    if options.log_bodies {
+                        require_feature(&field, cfg!(feature = "tracing"), "tracing")?;
+                    }
+                }
+                "rate_limit" => {
+                    options.rate_limit = Some(input.parse()?);
+                    require_feature(&field, cfg!(feature = "rate_limit"), "rate_limit")?;
+                }
+                "json_backend" => {
+                    options.json_backend = input.parse()?;
+                    if options.json_backend == JsonBackend::PathToError {
+                        require_feature(&field, cfg!(feature = "path_to_error"), "path_to_error")?;
+                    }
+                }
+                "strict_json" => {
+                    options.strict_json = input.parse::<LitBool>()?.value;
+                    if options.strict_json {
+                        require_feature(&field, cfg!(feature = "serde_ignored"), "serde_ignored")?;
+                    }
+                }
+                _ => return Err(syn::Error::new(field.span(), "unexpected provider option")),
+            }
+
+            input.parse::<Token![,]>()?;
+        }
+
+        Ok(options)
+    }
+}
+
 /// Represents a single API endpoint configuration, ordered by importance.
 ///
 /// The order below reflects the typical essential elements of an API endpoint:
@@ -92,6 +775,58 @@ pub struct HttpProviderInput {
 /// * `headers` - Optional custom headers type
 /// * `query_params` - Optional query parameters type
 /// * `path_params` - Optional path parameters type
+/// * `deprecated` - Optional deprecation note emitted as `#[deprecated]`
+/// * `validate_with` - Optional function validating the request body before sending
+/// * `body_format` - How the request body is encoded (defaults to `json`), or a
+///   list of formats the caller picks from at runtime
+/// * `static_headers` - Optional fixed headers sent with every request
+/// * `json_recursion_limit` - Optional maximum nesting depth accepted in JSON responses
+/// * `res_format` - How the response is returned (defaults to `json`)
+/// * `status_ok_with` - Optional function deciding from the `reqwest::StatusCode`
+///   whether a response is a success
+/// * `own_body` - Whether the request body is taken by value (defaults to `false`)
+/// * `default_on_status` - Optional statuses answered with `Default::default()`
+///   instead of an error
+/// * `sign_body` - Optional HMAC-SHA256 signature of the body sent as a header
+/// * `host` - Optional absolute base URL used instead of the provider's URL
+/// * `extra_headers` - Whether the methods take a runtime slice of extra headers
+/// * `query_types` - Optional inline query parameters, each becoming a method argument
+/// * `err` - Optional type the error body is deserialized into, optionally only
+///   on the listed statuses
+/// * `batch` - Whether the endpoint sends a `Vec` of operations and expects one
+///   result per operation, in order
+/// * `graphql` - Whether the response is a GraphQL `{ data, errors }` envelope
+/// * `paginate` - Optional cursor pagination, generating `*_page` and `*_all`
+/// * `request_id` - Whether a fresh `X-Request-Id` UUID is sent, overriding the
+///   provider's `request_id`
+/// * `typed_headers` - Whether `headers` is a typed `headers::Header` instead of
+///   a `HeaderMap`
+/// * `cfg` - Optional `cfg` predicate, e.g. `feature = "premium"`, the
+///   endpoint's methods are compiled under
+/// * `map_err` - Optional function converting the endpoint's errors, with the
+///   type it returns when that is not the generated error
+/// * `res_map` - Optional function transforming the response, with the type it
+///   returns when that is not the response type
+/// * `timeout` - Optional request timeout in milliseconds overriding the
+///   provider's, parsed from `1500`, `1500ms`, `30s` or `2m`
+/// * `query_defaults` - Query parameters added with a default value when a
+///   call leaves them out
+/// * `query_body` - Optional body-shaped type serialized into the query string,
+///   like `query_params`
+/// * `accept_encoding` - Optional `Accept-Encoding` header value advertised by
+///   the endpoint
+/// * `timeout_mul` - Optional factor the provider's timeout is multiplied by for
+///   the endpoint
+/// * `if_match` - Whether the methods take an `etag` sent as `If-Match`, failing
+///   with `PreconditionFailed` on a 412
+/// * `measure` - Whether the methods also return the `std::time::Duration` the
+///   request took
+/// * `path_params_map` - Whether `path_params: map` was given, so the path
+///   parameters are looked up by name in a `HashMap<String, String>`
+/// * `location` - Whether the methods also return the `Location` header of the
+///   response
+/// * `check_status` - Whether a non-success status fails the call (defaults to
+///   `true`)
 pub struct EndpointDef {
     pub method: HttpMethod,
     pub res: Option<Type>,
@@ -102,29 +837,341 @@ pub struct EndpointDef {
     pub headers: Option<Type>,
     pub query_params: Option<Type>,
     pub path_params: Option<Type>,
+    pub deprecated: Option<LitStr>,
+    pub validate_with: Option<Path>,
+    pub body_format: BodyFormat,
+    pub runtime_body_formats: Vec<BodyFormat>,
+    pub static_headers: Vec<(LitStr, LitStr)>,
+    pub json_recursion_limit: Option<usize>,
+    pub res_format: ResFormat,
+    pub status_ok_with: Option<Path>,
+    pub own_body: bool,
+    pub default_on_status: Vec<u16>,
+    pub sign_body: Option<SignBody>,
+    pub host: Option<LitStr>,
+    pub extra_headers: bool,
+    pub query_types: Vec<(Ident, Type)>,
+    pub err: Option<ErrorBody>,
+    pub batch: bool,
+    pub graphql: bool,
+    pub paginate: Option<Paginate>,
+    pub request_id: Option<bool>,
+    pub typed_headers: bool,
+    pub cfg: Option<syn::Meta>,
+    pub map_err: Option<MapFn>,
+    pub res_map: Option<MapFn>,
+    pub timeout: Option<u64>,
+    pub query_defaults: Vec<(String, String)>,
+    pub query_body: Option<Type>,
+    pub accept_encoding: Option<LitStr>,
+    pub timeout_mul: Option<u32>,
+    pub if_match: bool,
+    pub measure: bool,
+    pub path_params_map: bool,
+    pub location: bool,
+    pub check_status: bool,
 }
 
 impl Parse for HttpProviderInput {
     /// Parses the complete macro input into a structured form.
     ///
     /// Expects input in the format:
-    /// `struct_name, { endpoint1, endpoint2, ... }`
+    /// `struct_name, option: value, ..., { endpoint1, endpoint2, ... }`
+    ///
+    /// Each entry is either an endpoint block or a `resource: "/path" { ... }`
+    /// shorthand, see [`ResourceDef`].
     fn parse(input: ParseStream) -> Result<Self> {
         let struct_name: Ident = input.parse()?;
+        let state = if input.peek(Token![<]) {
+            input.parse::<Token![<]>()?;
+            let state: Ident = input.parse()?;
+            if !input.peek(Token![>]) {
+                return Err(syn::Error::new(
+                    input.span(),
+                    "expected a single state type parameter, e.g. `ApiClient<S>`",
+                ));
+            }
+            input.parse::<Token![>]>()?;
+            Some(state)
+        } else {
+            None
+        };
         input.parse::<Token![,]>()?;
+        let options = ProviderOptions::parse_until_endpoints(input)?;
 
         let content;
         braced!(content in input);
-        let items: Punctuated<EndpointDef, Token![,]> =
-            content.parse_terminated(EndpointDef::parse, Token![,])?;
+        let mut endpoints = Vec::new();
+        while !content.is_empty() {
+            if content.peek(Ident) {
+                let resource: ResourceDef = content.parse()?;
+                endpoints.extend(resource.into_endpoints());
+            } else {
+                endpoints.push(content.parse()?);
+            }
 
-        Ok(Self {
+            if content.is_empty() {
+                break;
+            }
+            content.parse::<Token![,]>()?;
+        }
+
+        let input = Self {
             struct_name,
-            endpoints: items.into_iter().collect(),
+            state,
+            options,
+            endpoints,
+        };
+        input.validate_fn_names()?;
+        Ok(input)
+    }
+}
+
+impl HttpProviderInput {
+    /// Rejects endpoints whose method name is not a valid identifier, which
+    /// would otherwise abort the expansion. The name may be derived from a
+    /// custom method such as `custom("1X")`, or be made invalid by the
+    /// `fn_prefix` or `fn_suffix`, e.g. `fn_prefix: "a-b"`.
+    fn validate_fn_names(&self) -> Result<()> {
+        let invalid = |name: &str, span: proc_macro2::Span, what: &str, fix: &str| {
+            syn::Error::new(
+                span,
+                format!(
+                    "the method name `{}` {} is not a valid identifier; {}",
+                    name, what, fix
+                ),
+            )
+        };
+        let prefix = self.options.fn_prefix.as_ref();
+        let suffix = self.options.fn_suffix.as_ref();
+
+        for def in &self.endpoints {
+            let name = match (&def.fn_name, &def.method) {
+                (Some(fn_name), _) => fn_name.to_string(),
+                (None, HttpMethod::Custom(verb)) => {
+                    let name = FnNameExpander::new(def, &self.options).derived_name();
+                    if syn::parse_str::<Ident>(&name).is_err() {
+                        return Err(invalid(
+                            &name,
+                            verb.span(),
+                            "derived from this method",
+                            "set `fn_name`",
+                        ));
+                    }
+                    name
+                }
+                (None, _) => FnNameExpander::new(def, &self.options).derived_name(),
+            };
+            let name = match prefix {
+                Some(prefix) => {
+                    let name = format!("{}{}", prefix.value(), name);
+                    if syn::parse_str::<Ident>(&name).is_err() {
+                        return Err(invalid(
+                            &name,
+                            prefix.span(),
+                            "with this prefix",
+                            "choose another prefix",
+                        ));
+                    }
+                    name
+                }
+                None => name,
+            };
+            if let Some(suffix) = suffix {
+                let name = format!("{}{}", name, suffix.value());
+                if syn::parse_str::<Ident>(&name).is_err() {
+                    return Err(invalid(
+                        &name,
+                        suffix.span(),
+                        "with this suffix",
+                        "choose another suffix",
+                    ));
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Shorthand for a standard REST resource.
+///
+/// ```ignore
+/// resource: "/users" {
+///     path_params: UserPath,  // required
+///     item: User,             // required
+///     list: Vec<User>,        // optional, defaults to Vec<item>
+///     req: NewUser,           // optional, defaults to item
+///     param: id,              // optional, defaults to id
+/// }
+/// ```
+///
+/// Expands to `GET /users`, `POST /users`, `GET /users/{id}`,
+/// `PUT /users/{id}` and `DELETE /users/{id}`.
+pub struct ResourceDef {
+    pub path: LitStr,
+    pub path_params: Type,
+    pub item: Type,
+    pub list: Option<Type>,
+    pub req: Option<Type>,
+    pub param: Option<Ident>,
+}
+
+impl ResourceDef {
+    /// Expands the resource into its collection and item endpoints.
+    pub fn into_endpoints(self) -> Vec<EndpointDef> {
+        let item = self.item;
+        let list = self
+            .list
+            .unwrap_or_else(|| syn::parse_quote! { Vec<#item> });
+        let req = self.req.unwrap_or_else(|| item.clone());
+        let param = self
+            .param
+            .map_or_else(|| "id".to_string(), |param| param.to_string());
+
+        let collection_path = self.path;
+        let item_path = LitStr::new(
+            &format!(
+                "{}/{{{}}}",
+                collection_path.value().trim_end_matches('/'),
+                param
+            ),
+            collection_path.span(),
+        );
+
+        let mut list_def = EndpointDef::new(HttpMethod::GET);
+        list_def.path = Some(collection_path.clone());
+        list_def.res = Some(list);
+
+        let mut create_def = EndpointDef::new(HttpMethod::POST);
+        create_def.path = Some(collection_path);
+        create_def.req = Some(req.clone());
+        create_def.res = Some(item.clone());
+
+        let mut get_def = EndpointDef::new(HttpMethod::GET);
+        get_def.path = Some(item_path.clone());
+        get_def.path_params = Some(self.path_params.clone());
+        get_def.res = Some(item.clone());
+
+        let mut update_def = EndpointDef::new(HttpMethod::PUT);
+        update_def.path = Some(item_path.clone());
+        update_def.path_params = Some(self.path_params.clone());
+        update_def.req = Some(req);
+        update_def.res = Some(item);
+
+        let mut delete_def = EndpointDef::new(HttpMethod::DELETE);
+        delete_def.path = Some(item_path);
+        delete_def.path_params = Some(self.path_params);
+
+        vec![list_def, create_def, get_def, update_def, delete_def]
+    }
+}
+
+impl Parse for ResourceDef {
+    /// Parses a `resource: "/path" { ... }` block.
+    fn parse(input: ParseStream) -> Result<Self> {
+        let keyword: Ident = input.parse()?;
+        if keyword != "resource" {
+            return Err(syn::Error::new(
+                keyword.span(),
+                "expected an endpoint block or `resource`",
+            ));
+        }
+        input.parse::<Token![:]>()?;
+        let path: LitStr = input.parse()?;
+
+        let content;
+        let brace = braced!(content in input);
+
+        let mut path_params = None;
+        let mut item = None;
+        let mut list = None;
+        let mut req = None;
+        let mut param = None;
+
+        while !content.is_empty() {
+            let field: Ident = content.parse()?;
+            content.parse::<Token![:]>()?;
+
+            match field.to_string().as_str() {
+                "path_params" => path_params = Some(content.parse()?),
+                "item" => item = Some(content.parse()?),
+                "list" => list = Some(content.parse()?),
+                "req" => req = Some(content.parse()?),
+                "param" => param = Some(content.parse()?),
+                _ => return Err(syn::Error::new(field.span(), "unexpected field")),
+            }
+
+            if content.peek(Token![,]) {
+                content.parse::<Token![,]>()?;
+            }
+        }
+
+        Ok(ResourceDef {
+            path,
+            path_params: path_params
+                .ok_or_else(|| syn::Error::new(brace.span.join(), "missing `path_params`"))?,
+            item: item.ok_or_else(|| syn::Error::new(brace.span.join(), "missing `item`"))?,
+            list,
+            req,
+            param,
         })
     }
 }
 
+impl EndpointDef {
+    /// Creates an endpoint with the given method and every optional field unset.
+    pub fn new(method: HttpMethod) -> Self {
+        Self {
+            method,
+            res: None,
+            path: None,
+            fn_name: None,
+            req: None,
+            headers: None,
+            query_params: None,
+            path_params: None,
+            deprecated: None,
+            validate_with: None,
+            body_format: BodyFormat::default(),
+            runtime_body_formats: Vec::new(),
+            static_headers: Vec::new(),
+            json_recursion_limit: None,
+            res_format: ResFormat::default(),
+            status_ok_with: None,
+            own_body: false,
+            default_on_status: Vec::new(),
+            sign_body: None,
+            host: None,
+            extra_headers: false,
+            query_types: Vec::new(),
+            err: None,
+            batch: false,
+            graphql: false,
+            paginate: None,
+            request_id: None,
+            typed_headers: false,
+            cfg: None,
+            map_err: None,
+            res_map: None,
+            timeout: None,
+            query_defaults: Vec::new(),
+            query_body: None,
+            accept_encoding: None,
+            timeout_mul: None,
+            if_match: false,
+            measure: false,
+            path_params_map: false,
+            location: false,
+            check_status: true,
+        }
+    }
+
+    /// Whether the generated methods take the request body by value.
+    pub fn owns_body(&self) -> bool {
+        self.own_body || self.body_format.is_owned()
+    }
+}
+
 impl Parse for EndpointDef {
     /// Parses a single endpoint definition block.
     ///
@@ -138,12 +1185,63 @@ impl Parse for EndpointDef {
     ///     res: ResponseType,         // optional, defaults to () if omitted
     ///     headers: HeadersType,      // optional
     ///     query_params: QueryType,   // optional
-    ///     path_params: ParamsType    // optional
+    ///     path_params: ParamsType,   // optional
+    ///     deprecated: "use v2",      // optional
+    ///     validate_with: my::check,  // optional
+    ///     body_format: stream,       // optional, defaults to json; or [json, form]
+    ///     static_headers: { "X-Api-Version": "2" }, // optional
+    ///     json_recursion_limit: 32,  // optional
+    ///     res_format: status,        // optional, defaults to json
+    ///     status_ok_with: my::is_ok, // optional, defaults to 2xx
+    ///     own_body: true,            // optional
+    ///     default_on_status: [404],  // optional
+    ///     sign_body: { header: "X-Signature", secret_field: hmac_secret }, // optional
+    ///     host: "https://auth.example.com", // optional
+    ///     extra_headers: true,       // optional
+    ///     query_types: { q: String, limit: Option<u32> }, // optional
+    ///     err: ApiError on [400, 422],  // optional
+    ///     batch: true,  // optional
+    ///     graphql: true,  // optional
+    ///     paginate: { cursor_field: "next" },  // optional
+    ///     request_id: true,  // optional
+    ///     typed_headers: true,  // optional
+    ///     cfg: feature = "premium", // optional
+    ///     map_err: to_domain -> DomainError, // optional
+    ///     res_map: normalize -> User,  // optional
+    ///     timeout: 30s,               // optional
+    ///     query_defaults: { limit: 20 }, // optional
+    ///     query_body: FilterType,    // optional
+    ///     accept_encoding: "gzip, br",  // optional
+    ///     timeout_mul: 3,             // optional
+    ///     if_match: true,             // optional
+    ///     measure: true,              // optional
+    ///     path_params: map,          // optional, instead of a type
+    ///     location: true,             // optional
+    ///     check_status: false,        // optional, defaults to true
     /// }
     /// ```
+    ///
+    /// An empty block is reported as such rather than as a missing `method`:
+    ///
+    /// ```compile_fail
+    /// use http_provider_macro::http_provider;
+    ///
+    /// http_provider!(
+    ///     UserApi,
+    ///     {
+    ///         {},
+    ///     }
+    /// );
+    /// ```
     fn parse(input: ParseStream) -> Result<Self> {
         let content;
-        braced!(content in input);
+        let brace = braced!(content in input);
+        if content.is_empty() {
+            return Err(syn::Error::new(
+                brace.span.join(),
+                "endpoint block is empty; at least `method` is required",
+            ));
+        }
 
         let mut path = None;
         let mut method = None;
@@ -153,6 +1251,39 @@ impl Parse for EndpointDef {
         let mut headers = None;
         let mut query_params = None;
         let mut path_params = None;
+        let mut deprecated = None;
+        let mut validate_with = None;
+        let mut body_format = None;
+        let mut runtime_body_formats = Vec::new();
+        let mut static_headers = Vec::new();
+        let mut json_recursion_limit = None;
+        let mut res_format = None;
+        let mut status_ok_with = None;
+        let mut own_body = false;
+        let mut default_on_status = Vec::new();
+        let mut sign_body = None;
+        let mut host = None;
+        let mut extra_headers = false;
+        let mut query_types = Vec::new();
+        let mut err = None;
+        let mut batch = false;
+        let mut graphql = false;
+        let mut paginate = None;
+        let mut request_id = None;
+        let mut typed_headers = false;
+        let mut cfg = None;
+        let mut map_err = None;
+        let mut res_map = None;
+        let mut timeout = None;
+        let mut query_defaults = Vec::new();
+        let mut query_body = None;
+        let mut accept_encoding = None;
+        let mut timeout_mul = None;
+        let mut if_match = false;
+        let mut measure = false;
+        let mut path_params_map = false;
+        let mut location = false;
+        let mut check_status = true;
 
         // Iteratively parse each key-value pair inside the endpoint block
         while !content.is_empty() {
@@ -167,7 +1298,102 @@ impl Parse for EndpointDef {
                 "res" => res = Some(content.parse()?),
                 "headers" => headers = Some(content.parse()?),
                 "query_params" => query_params = Some(content.parse()?),
-                "path_params" => path_params = Some(content.parse()?),
+                "deprecated" => deprecated = Some(content.parse()?),
+                "validate_with" => validate_with = Some(content.parse()?),
+                "body_format" if content.peek(syn::token::Bracket) => {
+                    runtime_body_formats = BodyFormat::parse_runtime_list(&content)?
+                }
+                "body_format" => {
+                    let format = content.parse()?;
+                    if format == BodyFormat::Multipart {
+                        require_feature(&field, cfg!(feature = "multipart"), "multipart")?;
+                    }
+                    body_format = Some(format);
+                }
+                "static_headers" => static_headers = parse_str_map(&content)?,
+                "json_recursion_limit" => {
+                    json_recursion_limit = Some(content.parse::<LitInt>()?.base10_parse()?)
+                }
+                "res_format" => {
+                    let format = content.parse()?;
+                    if format == ResFormat::Sse {
+                        require_feature(&field, cfg!(feature = "futures"), "futures")?;
+                    }
+                    res_format = Some(format);
+                }
+                "status_ok_with" => status_ok_with = Some(content.parse()?),
+                "own_body" => own_body = content.parse::<LitBool>()?.value,
+                "default_on_status" => default_on_status = parse_status_list(&content)?,
+                "sign_body" => {
+                    sign_body = Some(content.parse()?);
+                    require_feature(&field, cfg!(feature = "hmac"), "hmac")?;
+                }
+                "host" => host = Some(content.parse()?),
+                "extra_headers" => extra_headers = content.parse::<LitBool>()?.value,
+                "query_types" => query_types = parse_typed_fields(&content)?,
+                "err" => err = Some(content.parse()?),
+                "batch" => batch = content.parse::<LitBool>()?.value,
+                "graphql" => graphql = content.parse::<LitBool>()?.value,
+                "paginate" => {
+                    paginate = Some(content.parse()?);
+                    require_feature(&field, cfg!(feature = "futures"), "futures")?;
+                }
+                "request_id" => {
+                    request_id = Some(content.parse::<LitBool>()?.value);
+                    if request_id == Some(true) {
+                        require_feature(&field, cfg!(feature = "uuid"), "uuid")?;
+                    }
+                }
+                "typed_headers" => {
+                    typed_headers = content.parse::<LitBool>()?.value;
+                    if typed_headers {
+                        require_feature(&field, cfg!(feature = "headers"), "headers")?;
+                    }
+                }
+                "cfg" => cfg = Some(content.parse()?),
+                "map_err" => map_err = Some(content.parse()?),
+                "res_map" => res_map = Some(content.parse()?),
+                "timeout" => timeout = Some(parse_duration_ms(&content)?),
+                "query_defaults" => query_defaults = parse_query_defaults(&content)?,
+                "query_body" => query_body = Some(content.parse()?),
+                "accept_encoding" => {
+                    let value: LitStr = content.parse()?;
+                    if value.value().is_empty()
+                        || !value.value().bytes().all(|b| (0x20..0x7f).contains(&b))
+                    {
+                        return Err(syn::Error::new(
+                            value.span(),
+                            "expected a non-empty header value such as \"gzip, br\"",
+                        ));
+                    }
+                    accept_encoding = Some(value);
+                }
+                "timeout_mul" => {
+                    let lit: LitInt = content.parse()?;
+                    let mul: u32 = lit.base10_parse()?;
+                    if mul == 0 {
+                        return Err(syn::Error::new(
+                            lit.span(),
+                            "`timeout_mul` must be at least 1",
+                        ));
+                    }
+                    timeout_mul = Some(mul);
+                }
+                "if_match" => if_match = content.parse::<LitBool>()?.value,
+                "measure" => measure = content.parse::<LitBool>()?.value,
+                "path_params" => {
+                    let ty: Type = content.parse()?;
+                    if matches!(ty, Type::Path(ref p) if p.qself.is_none() && p.path.is_ident("map"))
+                    {
+                        path_params_map = true;
+                        path_params =
+                            Some(syn::parse_quote! { std::collections::HashMap<String, String> });
+                    } else {
+                        path_params = Some(ty);
+                    }
+                }
+                "location" => location = content.parse::<LitBool>()?.value,
+                "check_status" => check_status = content.parse::<LitBool>()?.value,
                 _ => return Err(syn::Error::new(field.span(), "unexpected field")),
             }
 
@@ -176,6 +1402,12 @@ impl Parse for EndpointDef {
             }
         }
 
+        let body_format = body_format.unwrap_or_default();
+        // A raw JSON body is a pre-serialized string unless another type is given
+        if req.is_none() && body_format == BodyFormat::RawJson {
+            req = Some(syn::parse_quote! { String });
+        }
+
         Ok(EndpointDef {
             path,
             method: method.ok_or_else(|| syn::Error::new(content.span(), "missing `method`"))?,
@@ -185,6 +1417,195 @@ impl Parse for EndpointDef {
             headers,
             query_params,
             path_params,
+            deprecated,
+            validate_with,
+            body_format,
+            runtime_body_formats,
+            static_headers,
+            json_recursion_limit,
+            res_format: res_format.unwrap_or_default(),
+            status_ok_with,
+            own_body,
+            default_on_status,
+            sign_body,
+            host,
+            extra_headers,
+            query_types,
+            err,
+            batch,
+            graphql,
+            paginate,
+            request_id,
+            typed_headers,
+            cfg,
+            map_err,
+            res_map,
+            timeout,
+            query_defaults,
+            query_body,
+            accept_encoding,
+            timeout_mul,
+            if_match,
+            measure,
+            path_params_map,
+            location,
+            check_status,
+        })
+    }
+}
+
+/// Parses a braced list of typed fields, e.g. `{ q: String, limit: Option<u32> }`.
+fn parse_typed_fields(input: ParseStream) -> Result<Vec<(Ident, Type)>> {
+    let content;
+    braced!(content in input);
+
+    let mut fields = Vec::new();
+    while !content.is_empty() {
+        let name: Ident = content.parse()?;
+        content.parse::<Token![:]>()?;
+        let ty: Type = content.parse()?;
+        fields.push((name, ty));
+        if content.peek(Token![,]) {
+            content.parse::<Token![,]>()?;
+        }
+    }
+    Ok(fields)
+}
+
+/// Parses a duration in milliseconds from an integer with an optional `ms`, `s`
+/// or `m` unit, e.g. `1500`, `1500ms`, `30s` or `2m`.
+fn parse_duration_ms(input: ParseStream) -> Result<u64> {
+    let lit: LitInt = input.parse()?;
+    let value: u64 = lit.base10_parse()?;
+    let factor = match lit.suffix() {
+        "" | "ms" => 1,
+        "s" => 1_000,
+        "m" => 60_000,
+        unit => {
+            return Err(syn::Error::new(
+                lit.span(),
+                format!("unsupported duration unit `{}`; use `ms`, `s` or `m`", unit),
+            ))
+        }
+    };
+    value
+        .checked_mul(factor)
+        .ok_or_else(|| syn::Error::new(lit.span(), "duration is too large"))
+}
+
+/// Parses a bracketed list of HTTP status codes, e.g. `[404, 410]`.
+fn parse_status_list(input: ParseStream) -> Result<Vec<u16>> {
+    let content;
+    bracketed!(content in input);
+    let lits = Punctuated::<LitInt, Token![,]>::parse_terminated(&content)?;
+
+    lits.iter()
+        .map(|lit| {
+            let status = lit.base10_parse::<u16>()?;
+            if !(100..=599).contains(&status) {
+                return Err(syn::Error::new(
+                    lit.span(),
+                    "HTTP status codes must be between 100 and 599",
+                ));
+            }
+            Ok(status)
+        })
+        .collect()
+}
+
+/// Parses a braced map of string literals, e.g. `{ "X-Api-Version": "2" }`.
+fn parse_str_map(input: ParseStream) -> Result<Vec<(LitStr, LitStr)>> {
+    let content;
+    braced!(content in input);
+
+    let mut entries = Vec::new();
+    while !content.is_empty() {
+        let key: LitStr = content.parse()?;
+        content.parse::<Token![:]>()?;
+        let value: LitStr = content.parse()?;
+        entries.push((key, value));
+
+        if content.peek(Token![,]) {
+            content.parse::<Token![,]>()?;
+        }
+    }
+
+    Ok(entries)
+}
+
+/// Parses DNS overrides such as `{ "api.example.com": "127.0.0.1:8080" }`,
+/// normalizing each address to a `SocketAddr` string. A bare IP address gets
+/// port 0, since the client connects to the port of the URL anyway.
+fn parse_resolve(input: ParseStream) -> Result<Vec<(LitStr, String)>> {
+    parse_str_map(input)?
+        .into_iter()
+        .map(|(domain, addr)| {
+            let value = addr.value();
+            let socket_addr = value
+                .parse::<std::net::SocketAddr>()
+                .or_else(|_| {
+                    value
+                        .parse::<std::net::IpAddr>()
+                        .map(|ip| std::net::SocketAddr::new(ip, 0))
+                })
+                .map_err(|_| {
+                    syn::Error::new(
+                        addr.span(),
+                        "expected an IP address such as \"127.0.0.1\" or \"127.0.0.1:8080\"",
+                    )
+                })?;
+            Ok((domain, socket_addr.to_string()))
         })
+        .collect()
+}
+
+/// Parses query defaults such as `{ limit: 20, "sort-by": "name" }` into
+/// their key and value as sent in the query string.
+fn parse_query_defaults(input: ParseStream) -> Result<Vec<(String, String)>> {
+    let content;
+    braced!(content in input);
+
+    let mut entries = Vec::new();
+    while !content.is_empty() {
+        let key = if content.peek(LitStr) {
+            content.parse::<LitStr>()?.value()
+        } else {
+            content.parse::<Ident>()?.to_string()
+        };
+        content.parse::<Token![:]>()?;
+        let value = match content.parse::<Lit>()? {
+            Lit::Str(lit) => lit.value(),
+            Lit::Int(lit) => lit.base10_digits().to_string(),
+            Lit::Float(lit) => lit.base10_digits().to_string(),
+            Lit::Bool(lit) => lit.value.to_string(),
+            lit => {
+                return Err(syn::Error::new(
+                    lit.span(),
+                    "query defaults must be string, integer, float or bool literals",
+                ))
+            }
+        };
+        entries.push((key, value));
+
+        if content.peek(Token![,]) {
+            content.parse::<Token![,]>()?;
+        }
+    }
+
+    Ok(entries)
+}
+
+/// Errors at the option's span when the crate feature it depends on is disabled.
+/// Boolean options only depend on it when enabled, so `false` always parses.
+fn require_feature(field: &Ident, enabled: bool, feature: &str) -> Result<()> {
+    if enabled {
+        return Ok(());
     }
+    Err(syn::Error::new(
+        field.span(),
+        format!(
+            "`{}` requires the `{}` feature of http-provider-macro",
+            field, feature
+        ),
+    ))
 }